@@ -1,4 +1,14 @@
+mod astar;
+mod bfs;
+mod bowtie;
+mod components;
 mod dijkstra;
 mod edit;
+mod geodesics;
+mod pagerank;
+mod yen;
 
-pub use self::{dijkstra::*, edit::*};
+pub use self::{
+    astar::*, bfs::*, bowtie::*, components::*, dijkstra::*, edit::*, geodesics::*, pagerank::*,
+    yen::*,
+};
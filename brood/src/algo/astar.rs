@@ -0,0 +1,96 @@
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use crate::graph::{EdgeIdx, Graph, NodeIdx};
+
+/// Searches for a shortest path from a start node to a goal node, guided by an
+/// estimate of the remaining cost.
+///
+/// The estimate must never exceed the actual cost of reaching the goal, and
+/// must not drop by more than an edge's cost when following that edge.
+/// Otherwise, the path found may not be the shortest.
+///
+/// Like [`super::Dijkstra`], the buffers are reused between runs.
+pub struct AStar<'a> {
+    graph: &'a Graph,
+    cost: Vec<u32>,
+    pred: Vec<NodeIdx>,
+    /// Nodes whose cost was set during the current run.
+    reached: Vec<NodeIdx>,
+}
+
+impl<'a> AStar<'a> {
+    pub fn new(graph: &'a Graph) -> Self {
+        Self {
+            graph,
+            cost: vec![u32::MAX; graph.nodes.len()],
+            pred: vec![NodeIdx::NONE; graph.nodes.len()],
+            reached: vec![],
+        }
+    }
+
+    fn reset(&mut self) {
+        for node in self.reached.drain(..) {
+            self.cost[node.usize()] = u32::MAX;
+            self.pred[node.usize()] = NodeIdx::NONE;
+        }
+    }
+
+    /// Find the cost and nodes of a shortest path from start to goal.
+    ///
+    /// Edges costing `u32::MAX` are never taken.
+    pub fn run(
+        &mut self,
+        start: NodeIdx,
+        goal: NodeIdx,
+        estimate: impl Fn(NodeIdx) -> u32,
+        cost: impl Fn(NodeIdx, EdgeIdx, NodeIdx) -> u32,
+    ) -> Option<(u32, Vec<NodeIdx>)> {
+        self.reset();
+        self.cost[start.usize()] = 0;
+        self.reached.push(start);
+        let mut queue = BinaryHeap::new();
+        queue.push((Reverse(estimate(start)), start));
+
+        while let Some((Reverse(curr_total), curr)) = queue.pop() {
+            if curr == goal {
+                break;
+            }
+
+            let curr_cost = self.cost[curr.usize()];
+            if curr_total > curr_cost.saturating_add(estimate(curr)) {
+                continue; // Outdated entry
+            }
+
+            for (edge, next) in self.graph.edge_targets(curr) {
+                let next_cost = curr_cost.saturating_add(cost(curr, edge, next));
+                if next_cost < self.cost[next.usize()] {
+                    if self.cost[next.usize()] == u32::MAX {
+                        self.reached.push(next);
+                    }
+                    self.cost[next.usize()] = next_cost;
+                    self.pred[next.usize()] = curr;
+                    queue.push((Reverse(next_cost.saturating_add(estimate(next))), next));
+                }
+            }
+        }
+
+        let goal_cost = self.cost[goal.usize()];
+        if goal_cost == u32::MAX {
+            return None;
+        }
+
+        let mut path = vec![];
+        let mut at = goal;
+        while at != NodeIdx::NONE {
+            path.push(at);
+            at = self.pred[at.usize()];
+        }
+        path.reverse();
+        Some((goal_cost, path))
+    }
+
+    /// The number of nodes reached during the last run.
+    pub fn reached(&self) -> usize {
+        self.reached.len()
+    }
+}
@@ -0,0 +1,280 @@
+use std::collections::VecDeque;
+
+use crate::graph::{EdgeIdx, Graph, NodeIdx};
+
+/// One direction of a [`BidiBfs`].
+struct Side {
+    cost: Vec<u32>,
+    /// The previous node on the path when searching forward, the next node
+    /// when searching backward.
+    via: Vec<NodeIdx>,
+    /// Ordered by cost, may contain outdated entries.
+    queue: VecDeque<(u32, NodeIdx)>,
+    /// Nodes whose cost was set during the current run.
+    reached: Vec<NodeIdx>,
+}
+
+impl Side {
+    fn new(len: usize) -> Self {
+        Self {
+            cost: vec![u32::MAX; len],
+            via: vec![NodeIdx::NONE; len],
+            queue: VecDeque::new(),
+            reached: vec![],
+        }
+    }
+
+    fn reset(&mut self) {
+        for node in self.reached.drain(..) {
+            self.cost[node.usize()] = u32::MAX;
+            self.via[node.usize()] = NodeIdx::NONE;
+        }
+        self.queue.clear();
+    }
+
+    #[inline]
+    fn cost(&self, node: NodeIdx) -> u32 {
+        self.cost[node.usize()]
+    }
+
+    /// Returns whether the node's cost was improved.
+    fn relax(&mut self, node: NodeIdx, cost: u32, via: NodeIdx, zero: bool) -> bool {
+        if cost >= self.cost(node) {
+            return false;
+        }
+
+        if self.cost(node) == u32::MAX {
+            self.reached.push(node);
+        }
+        self.cost[node.usize()] = cost;
+        self.via[node.usize()] = via;
+        if zero {
+            self.queue.push_front((cost, node));
+        } else {
+            self.queue.push_back((cost, node));
+        }
+        true
+    }
+
+    /// The lowest cost of any node in the queue, or `u32::MAX` if it is empty.
+    fn front(&mut self) -> u32 {
+        while let Some((cost, node)) = self.queue.front() {
+            if *cost == self.cost(*node) {
+                return *cost;
+            }
+            self.queue.pop_front(); // Outdated entry
+        }
+        u32::MAX
+    }
+}
+
+/// Searches for a shortest path from both ends at once until the searches
+/// meet in the middle.
+///
+/// Edges may only cost 0 or 1, which allows a double-ended queue instead of a
+/// priority queue. Edges costing `u32::MAX` are never taken. Searching backward
+/// requires the in-edges of the graph, see [`Graph::build_reverse`].
+///
+/// Like [`super::Dijkstra`], the buffers are reused between runs.
+pub struct BidiBfs<'a> {
+    graph: &'a Graph,
+    forward: Side,
+    backward: Side,
+}
+
+impl<'a> BidiBfs<'a> {
+    pub fn new(graph: &'a Graph) -> Self {
+        Self {
+            graph,
+            forward: Side::new(graph.nodes.len()),
+            backward: Side::new(graph.nodes.len()),
+        }
+    }
+
+    /// Find the cost and nodes of a shortest path from start to goal.
+    pub fn run(
+        &mut self,
+        start: NodeIdx,
+        goal: NodeIdx,
+        cost: impl Fn(NodeIdx, EdgeIdx, NodeIdx) -> u32,
+    ) -> Option<(u32, Vec<NodeIdx>)> {
+        self.forward.reset();
+        self.backward.reset();
+        self.forward.relax(start, 0, NodeIdx::NONE, true);
+        self.backward.relax(goal, 0, NodeIdx::NONE, true);
+
+        let mut best = if start == goal { 0 } else { u32::MAX };
+        let mut meet = start;
+
+        loop {
+            let forward_front = self.forward.front();
+            let backward_front = self.backward.front();
+
+            // Once either search is exhausted, every path it could have taken
+            // has already been found.
+            if forward_front == u32::MAX || backward_front == u32::MAX {
+                break;
+            }
+
+            // No path through the remaining nodes can be cheaper.
+            if forward_front + backward_front >= best {
+                break;
+            }
+
+            // Expand the smaller search to keep both about the same size.
+            if self.forward.queue.len() <= self.backward.queue.len() {
+                let (curr_cost, curr) = self.forward.queue.pop_front().unwrap();
+                for (edge, next) in self.graph.edge_targets(curr) {
+                    let edge_cost = cost(curr, edge, next);
+                    if edge_cost == u32::MAX {
+                        continue;
+                    }
+                    debug_assert!(edge_cost <= 1, "edges may only cost 0 or 1");
+                    let next_cost = curr_cost + edge_cost;
+                    if !self.forward.relax(next, next_cost, curr, edge_cost == 0) {
+                        continue;
+                    }
+                    let remaining = self.backward.cost(next);
+                    if remaining != u32::MAX && next_cost + remaining < best {
+                        best = next_cost + remaining;
+                        meet = next;
+                    }
+                }
+            } else {
+                let (curr_cost, curr) = self.backward.queue.pop_front().unwrap();
+                for (edge, prev) in self.graph.in_edges(curr) {
+                    let edge_cost = cost(prev, edge, curr);
+                    if edge_cost == u32::MAX {
+                        continue;
+                    }
+                    debug_assert!(edge_cost <= 1, "edges may only cost 0 or 1");
+                    let prev_cost = curr_cost + edge_cost;
+                    if !self.backward.relax(prev, prev_cost, curr, edge_cost == 0) {
+                        continue;
+                    }
+                    let remaining = self.forward.cost(prev);
+                    if remaining != u32::MAX && prev_cost + remaining < best {
+                        best = prev_cost + remaining;
+                        meet = prev;
+                    }
+                }
+            }
+        }
+
+        if best == u32::MAX {
+            return None;
+        }
+
+        let mut path = vec![];
+        let mut at = meet;
+        while at != NodeIdx::NONE {
+            path.push(at);
+            at = self.forward.via[at.usize()];
+        }
+        path.reverse();
+
+        let mut at = self.backward.via[meet.usize()];
+        while at != NodeIdx::NONE {
+            path.push(at);
+            at = self.backward.via[at.usize()];
+        }
+
+        Some((best, path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algo::Dijkstra;
+
+    /// A fixed pseudo-random graph with some dead ends and unreachable nodes.
+    fn graph() -> Graph {
+        let mut state = 12345_u32;
+        let mut random = |n: u32| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            (state >> 16) % n
+        };
+
+        let len = 60;
+        let mut graph = Graph::default();
+        for _ in 0..len {
+            graph.add_node();
+            let mut targets = (0..random(4)).map(|_| random(len)).collect::<Vec<_>>();
+            targets.sort_unstable();
+            for target in targets {
+                graph.add_edge(NodeIdx(target));
+            }
+        }
+        graph.build_reverse();
+        graph
+    }
+
+    /// Check the path's edges and cost, then compare the cost with dijkstra.
+    /// Returns the highest cost found.
+    fn check_all_pairs(
+        graph: &Graph,
+        cost: impl Fn(NodeIdx, EdgeIdx, NodeIdx) -> u32 + Copy,
+    ) -> u32 {
+        let mut max_cost = 0;
+        let mut bfs = BidiBfs::new(graph);
+        let mut dijkstra = Dijkstra::new(graph);
+        for start in graph.nodes() {
+            dijkstra.run(start, |_| false, cost);
+            for goal in graph.nodes() {
+                let expected = Some(dijkstra.cost(goal)).filter(|c| *c < u32::MAX);
+                let found = bfs.run(start, goal, cost);
+                assert_eq!(
+                    found.as_ref().map(|(c, _)| *c),
+                    expected,
+                    "{start:?} to {goal:?}"
+                );
+
+                let Some((found_cost, path)) = found else {
+                    continue;
+                };
+                assert_eq!((path[0], *path.last().unwrap()), (start, goal));
+                let path_cost = path
+                    .windows(2)
+                    .map(|pair| {
+                        graph
+                            .edge_targets(pair[0])
+                            .filter(|(_, target)| *target == pair[1])
+                            .map(|(edge, target)| cost(pair[0], edge, target))
+                            .min()
+                            .expect("path follows a missing edge")
+                    })
+                    .sum::<u32>();
+                assert_eq!(path_cost, found_cost);
+                max_cost = max_cost.max(found_cost);
+            }
+        }
+        max_cost
+    }
+
+    #[test]
+    fn hops_match_dijkstra() {
+        // Long enough paths for both searches to do some work.
+        assert!(check_all_pairs(&graph(), |_, _, _| 1) >= 4);
+    }
+
+    #[test]
+    fn zero_cost_edges_match_dijkstra() {
+        // Every fifth node acts like a redirect.
+        check_all_pairs(&graph(), |source, _, _| (source.0 % 5 != 0) as u32);
+    }
+
+    #[test]
+    fn blocked_edges_match_dijkstra() {
+        check_all_pairs(
+            &graph(),
+            |_, _, target| {
+                if target.0 % 7 == 3 {
+                    u32::MAX
+                } else {
+                    1
+                }
+            },
+        );
+    }
+}
@@ -0,0 +1,142 @@
+use crate::graph::{Graph, NodeIdx};
+
+use super::Components;
+
+/// The parts of a graph relative to its largest strongly connected component.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Region {
+    /// The largest strongly connected component.
+    Core,
+    /// Nodes that can reach the core.
+    In,
+    /// Nodes reachable from the core.
+    Out,
+    /// Nodes reachable from [`Self::In`] that can't reach [`Self::Out`].
+    InTendrils,
+    /// Nodes that can reach [`Self::Out`] but aren't reachable from
+    /// [`Self::In`].
+    OutTendrils,
+    /// Nodes reachable from [`Self::In`] that can reach [`Self::Out`] without
+    /// passing through the core.
+    Tubes,
+    /// Everything else.
+    Disconnected,
+}
+
+impl Region {
+    pub const ALL: [Self; 7] = [
+        Self::Core,
+        Self::In,
+        Self::Out,
+        Self::InTendrils,
+        Self::OutTendrils,
+        Self::Tubes,
+        Self::Disconnected,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Core => "core",
+            Self::In => "in",
+            Self::Out => "out",
+            Self::InTendrils => "in-tendrils",
+            Self::OutTendrils => "out-tendrils",
+            Self::Tubes => "tubes",
+            Self::Disconnected => "disconnected",
+        }
+    }
+}
+
+/// Mark every node reachable from the seeds while only entering nodes the
+/// filter accepts, either following edges forward or backward.
+///
+/// Searching backward requires the in-edges, see [`Graph::build_reverse`].
+fn spread(
+    graph: &Graph,
+    seeds: impl IntoIterator<Item = NodeIdx>,
+    backward: bool,
+    enter: impl Fn(NodeIdx) -> bool,
+) -> Vec<bool> {
+    let mut reached = vec![false; graph.nodes.len()];
+    let mut stack = vec![];
+    for seed in seeds {
+        if !reached[seed.usize()] {
+            reached[seed.usize()] = true;
+            stack.push(seed);
+        }
+    }
+
+    let mut visit = |next: NodeIdx, stack: &mut Vec<NodeIdx>| {
+        if !reached[next.usize()] && enter(next) {
+            reached[next.usize()] = true;
+            stack.push(next);
+        }
+    };
+
+    while let Some(node) = stack.pop() {
+        if backward {
+            for (_, prev) in graph.in_edges(node) {
+                visit(prev, &mut stack);
+            }
+        } else {
+            for next in graph.targets(node) {
+                visit(next, &mut stack);
+            }
+        }
+    }
+
+    reached
+}
+
+/// The bow-tie decomposition of a graph, which sorts every node into a
+/// [`Region`].
+///
+/// Requires the in-edges, see [`Graph::build_reverse`].
+pub fn bow_tie(graph: &Graph, components: &Components) -> Vec<Region> {
+    let Some(core) = (0..components.count() as u32).max_by_key(|c| components.size(*c)) else {
+        return vec![];
+    };
+    let in_core = |node: NodeIdx| components.of(node) == core;
+    let core_nodes = graph.nodes().filter(|n| in_core(*n)).collect::<Vec<_>>();
+
+    let from_core = spread(graph, core_nodes.iter().copied(), false, |_| true);
+    let to_core = spread(graph, core_nodes.iter().copied(), true, |_| true);
+
+    let mut regions = graph
+        .nodes()
+        .map(|node| {
+            match (
+                in_core(node),
+                to_core[node.usize()],
+                from_core[node.usize()],
+            ) {
+                (true, _, _) => Region::Core,
+                (false, true, _) => Region::In,
+                (false, _, true) => Region::Out,
+                _ => Region::Disconnected,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    // The remaining nodes are sorted by whether they are connected to the in
+    // and out regions without passing through the core.
+    let rest = |node: NodeIdx| regions[node.usize()] == Region::Disconnected;
+    let in_nodes = graph.nodes().filter(|n| regions[n.usize()] == Region::In);
+    let from_in = spread(graph, in_nodes, false, rest);
+    let out_nodes = graph.nodes().filter(|n| regions[n.usize()] == Region::Out);
+    let to_out = spread(graph, out_nodes, true, rest);
+
+    for node in graph.nodes() {
+        if regions[node.usize()] != Region::Disconnected {
+            continue;
+        }
+        regions[node.usize()] = match (from_in[node.usize()], to_out[node.usize()]) {
+            (true, true) => Region::Tubes,
+            (true, false) => Region::InTendrils,
+            (false, true) => Region::OutTendrils,
+            (false, false) => Region::Disconnected,
+        };
+    }
+
+    regions
+}
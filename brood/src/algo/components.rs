@@ -0,0 +1,243 @@
+use std::collections::HashMap;
+
+use crate::graph::{Graph, NodeIdx, Targets};
+
+const UNVISITED: u32 = u32::MAX;
+
+/// The root of a node's set in a union-find forest, halving the path to it.
+fn find(parent: &mut [u32], mut node: u32) -> u32 {
+    while parent[node as usize] != node {
+        let grandparent = parent[parent[node as usize] as usize];
+        parent[node as usize] = grandparent;
+        node = grandparent;
+    }
+    node
+}
+
+/// A partition of the nodes of a graph into components.
+pub struct Components {
+    component: Vec<u32>,
+    sizes: Vec<usize>,
+}
+
+impl Components {
+    /// Find the strongly connected components, in which every node can reach
+    /// every other node.
+    ///
+    /// Components are numbered in topological order, so edges between
+    /// components always lead to a higher number.
+    ///
+    /// This is Tarjan's algorithm, but with an explicit stack instead of
+    /// recursion, since paths through the graph can be far longer than the
+    /// call stack allows.
+    pub fn strong(graph: &Graph) -> Self {
+        let len = graph.nodes.len();
+        let mut index = vec![UNVISITED; len];
+        let mut lowlink = vec![0_u32; len];
+        let mut on_stack = vec![false; len];
+        let mut stack = vec![];
+        let mut component = vec![UNVISITED; len];
+        let mut sizes = vec![];
+        let mut next_index = 0_u32;
+
+        // The nodes currently being visited, and the edges still left to
+        // follow from each of them.
+        let mut visiting = Vec::<(NodeIdx, Targets<'_>)>::new();
+
+        for root in graph.nodes() {
+            if index[root.usize()] != UNVISITED {
+                continue;
+            }
+
+            index[root.usize()] = next_index;
+            lowlink[root.usize()] = next_index;
+            next_index += 1;
+            stack.push(root);
+            on_stack[root.usize()] = true;
+            visiting.push((root, graph.targets(root)));
+
+            while let Some((node, targets)) = visiting.last_mut() {
+                let node = *node;
+
+                if let Some(next) = targets.next() {
+                    if index[next.usize()] == UNVISITED {
+                        index[next.usize()] = next_index;
+                        lowlink[next.usize()] = next_index;
+                        next_index += 1;
+                        stack.push(next);
+                        on_stack[next.usize()] = true;
+                        visiting.push((next, graph.targets(next)));
+                    } else if on_stack[next.usize()] {
+                        lowlink[node.usize()] = lowlink[node.usize()].min(index[next.usize()]);
+                    }
+                    continue;
+                }
+
+                visiting.pop();
+                if let Some((parent, _)) = visiting.last() {
+                    lowlink[parent.usize()] = lowlink[parent.usize()].min(lowlink[node.usize()]);
+                }
+
+                if lowlink[node.usize()] == index[node.usize()] {
+                    let id = sizes.len() as u32;
+                    let mut size = 0;
+                    loop {
+                        let member = stack.pop().unwrap();
+                        on_stack[member.usize()] = false;
+                        component[member.usize()] = id;
+                        size += 1;
+                        if member == node {
+                            break;
+                        }
+                    }
+                    sizes.push(size);
+                }
+            }
+        }
+
+        // Tarjan's algorithm finishes a component only after all components
+        // reachable from it, so its order is the reverse topological order.
+        let count = sizes.len() as u32;
+        for id in &mut component {
+            *id = count - 1 - *id;
+        }
+        sizes.reverse();
+
+        Self { component, sizes }
+    }
+
+    /// Find the weakly connected components, in which every node is connected
+    /// to every other node when ignoring the direction of edges.
+    ///
+    /// Components are numbered in the order of their first node.
+    pub fn weak(graph: &Graph) -> Self {
+        // Union-find with union by size.
+        let mut parent = graph.nodes().map(|n| n.0).collect::<Vec<_>>();
+        let mut size = vec![1_usize; parent.len()];
+
+        for (source, target) in graph.edges() {
+            let mut a = find(&mut parent, source.0);
+            let mut b = find(&mut parent, target.0);
+            if a == b {
+                continue;
+            }
+            if size[a as usize] < size[b as usize] {
+                (a, b) = (b, a);
+            }
+            parent[b as usize] = a;
+            size[a as usize] += size[b as usize];
+        }
+
+        let mut ids = vec![UNVISITED; parent.len()];
+        let mut component = vec![];
+        let mut sizes = vec![];
+        for node in graph.nodes() {
+            let root = find(&mut parent, node.0) as usize;
+            if ids[root] == UNVISITED {
+                ids[root] = sizes.len() as u32;
+                sizes.push(0);
+            }
+            component.push(ids[root]);
+            sizes[ids[root] as usize] += 1;
+        }
+
+        Self { component, sizes }
+    }
+
+    /// The number of components.
+    pub fn count(&self) -> usize {
+        self.sizes.len()
+    }
+
+    pub fn of(&self, node: NodeIdx) -> u32 {
+        self.component[node.usize()]
+    }
+
+    /// The number of nodes in a component.
+    pub fn size(&self, component: u32) -> usize {
+        self.sizes[component as usize]
+    }
+
+    pub fn sizes(&self) -> &[usize] {
+        &self.sizes
+    }
+
+    /// The members of every component accepted by the filter.
+    pub fn members(&self, filter: impl Fn(u32) -> bool) -> HashMap<u32, Vec<NodeIdx>> {
+        let mut members = HashMap::<u32, Vec<NodeIdx>>::new();
+        for (i, id) in self.component.iter().enumerate() {
+            if filter(*id) {
+                members.entry(*id).or_default().push(NodeIdx::new(i));
+            }
+        }
+        members
+    }
+
+    /// The number of edges between every pair of different components that
+    /// are connected by at least one edge.
+    pub fn condensation(&self, graph: &Graph) -> HashMap<(u32, u32), usize> {
+        let mut edges = HashMap::new();
+        for (source, target) in graph.edges() {
+            let (source, target) = (self.of(source), self.of(target));
+            if source != target {
+                *edges.entry((source, target)).or_default() += 1;
+            }
+        }
+        edges
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two cycles joined by a node with a self-loop, a lone self-loop and an
+    /// isolated node.
+    fn graph() -> Graph {
+        Graph::from_adjacency(&[&[1], &[2], &[0, 3], &[3, 4], &[5], &[4], &[6], &[]])
+    }
+
+    #[test]
+    fn strong_cycles_and_self_loops() {
+        let graph = graph();
+        let components = Components::strong(&graph);
+        let of = |n| components.of(NodeIdx(n));
+
+        assert_eq!(components.count(), 5);
+        assert!(of(0) == of(1) && of(1) == of(2));
+        assert_eq!(of(4), of(5));
+        assert_eq!(components.size(of(0)), 3);
+        assert_eq!(components.size(of(3)), 1);
+        assert_eq!(components.size(of(6)), 1);
+        assert_ne!(of(6), of(7));
+
+        // Topological order
+        assert!(of(0) < of(3) && of(3) < of(4));
+
+        // Self-loops don't show up in the condensation.
+        let condensation = components.condensation(&graph);
+        assert_eq!(condensation.len(), 2);
+        assert_eq!(condensation[&(of(2), of(3))], 1);
+        assert_eq!(condensation[&(of(3), of(4))], 1);
+    }
+
+    #[test]
+    fn strong_long_cycle() {
+        // Far deeper than the call stack would allow with recursion.
+        let len = 200_000;
+        let mut graph = Graph::default();
+        for i in 0..len {
+            graph.add_node();
+            graph.add_edge(NodeIdx((i + 1) % len));
+        }
+        let components = Components::strong(&graph);
+        assert_eq!(components.sizes(), [len as usize]);
+    }
+
+    #[test]
+    fn weak_ignores_direction() {
+        let components = Components::weak(&graph());
+        assert_eq!(components.sizes(), [6, 1, 1]);
+        assert_eq!(components.of(NodeIdx(5)), 0);
+    }
+}
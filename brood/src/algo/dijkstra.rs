@@ -2,10 +2,16 @@ use std::{cmp::Reverse, collections::BinaryHeap};
 
 use crate::graph::{EdgeIdx, Graph, NodeIdx};
 
+/// Searches for shortest paths from a start node.
+///
+/// The buffers are reused between runs, so running many searches with the same
+/// instance only costs as much as the nodes each search reaches.
 pub struct Dijkstra<'a> {
     graph: &'a Graph,
     cost: Vec<u32>,
     pred: Vec<NodeIdx>,
+    /// Nodes whose cost was set during the current run.
+    reached: Vec<NodeIdx>,
 }
 
 impl<'a> Dijkstra<'a> {
@@ -14,39 +20,103 @@ impl<'a> Dijkstra<'a> {
             graph,
             cost: vec![u32::MAX; graph.nodes.len()],
             pred: vec![NodeIdx::NONE; graph.nodes.len()],
+            reached: vec![],
         }
     }
 
+    fn reset(&mut self) {
+        for node in self.reached.drain(..) {
+            self.cost[node.usize()] = u32::MAX;
+            self.pred[node.usize()] = NodeIdx::NONE;
+        }
+    }
+
+    /// Edges costing `u32::MAX` are never taken.
+    ///
+    /// Returns the first goal that was reached, if any.
     pub fn run(
         &mut self,
         start: NodeIdx,
         goal: impl Fn(NodeIdx) -> bool,
         cost: impl Fn(NodeIdx, EdgeIdx, NodeIdx) -> u32,
-    ) {
-        self.cost[start.usize()] = 0;
+    ) -> Option<NodeIdx> {
+        self.run_multi([start], goal, cost)
+    }
+
+    /// Like [`Self::run`], but starting from all start nodes at once.
+    ///
+    /// Paths lead back to the closest start node.
+    pub fn run_multi(
+        &mut self,
+        starts: impl IntoIterator<Item = NodeIdx>,
+        goal: impl Fn(NodeIdx) -> bool,
+        cost: impl Fn(NodeIdx, EdgeIdx, NodeIdx) -> u32,
+    ) -> Option<NodeIdx> {
+        self.reset();
         let mut queue = BinaryHeap::new();
-        queue.push((Reverse(0), start));
+        for start in starts {
+            if self.cost[start.usize()] == u32::MAX {
+                self.cost[start.usize()] = 0;
+                self.reached.push(start);
+                queue.push((Reverse(0_u32), start));
+            }
+        }
 
         while let Some((Reverse(curr_cost), curr)) = queue.pop() {
             if goal(curr) {
-                break; // We've found the shortest path to our target
+                return Some(curr); // We've found the shortest path to our target
             }
 
-            // These seem to never actually occur
-            // if curr_cost > self.cost[curr.usize()] {
-            //     continue; // Outdated entry
-            // }
+            if curr_cost > self.cost[curr.usize()] {
+                continue; // Outdated entry
+            }
 
-            for edge in self.graph.edge_range(curr).map(EdgeIdx::new) {
-                let next = self.graph.edges[edge.usize()];
-                let next_cost = curr_cost + cost(curr, edge, next);
+            for (edge, next) in self.graph.edge_targets(curr) {
+                let next_cost = curr_cost.saturating_add(cost(curr, edge, next));
                 if next_cost < self.cost[next.usize()] {
+                    if self.cost[next.usize()] == u32::MAX {
+                        self.reached.push(next);
+                    }
                     self.cost[next.usize()] = next_cost;
                     self.pred[next.usize()] = curr;
                     queue.push((Reverse(next_cost), next));
                 }
             }
         }
+
+        None
+    }
+
+    /// Find the cost of reaching the goal from every node by following the
+    /// edges backward.
+    ///
+    /// The predecessor of a node is then the next node on its path to the goal,
+    /// so [`Self::path`] returns paths in reverse. Requires the in-edges, see
+    /// [`Graph::build_reverse`].
+    pub fn run_backward(&mut self, goal: NodeIdx, cost: impl Fn(NodeIdx, EdgeIdx, NodeIdx) -> u32) {
+        self.reset();
+        self.cost[goal.usize()] = 0;
+        self.reached.push(goal);
+        let mut queue = BinaryHeap::new();
+        queue.push((Reverse(0_u32), goal));
+
+        while let Some((Reverse(curr_cost), curr)) = queue.pop() {
+            if curr_cost > self.cost[curr.usize()] {
+                continue; // Outdated entry
+            }
+
+            for (edge, prev) in self.graph.in_edges(curr) {
+                let prev_cost = curr_cost.saturating_add(cost(prev, edge, curr));
+                if prev_cost < self.cost[prev.usize()] {
+                    if self.cost[prev.usize()] == u32::MAX {
+                        self.reached.push(prev);
+                    }
+                    self.cost[prev.usize()] = prev_cost;
+                    self.pred[prev.usize()] = curr;
+                    queue.push((Reverse(prev_cost), prev));
+                }
+            }
+        }
     }
 
     #[inline]
@@ -1,29 +1,34 @@
 use std::mem;
 
 use crate::{
-    data::{Data, Link},
-    graph::NodeIdx,
+    data::{Data, Link, Load},
+    graph::{EdgeIdx, NodeIdx},
     util,
 };
 
 pub fn retain_edges(data: &mut Data, f: impl Fn(&Link) -> bool) {
-    let mut links = mem::take(&mut data.links).into_iter();
+    assert!(data.load >= Load::Flags, "link flags must be loaded");
+    data.graph_fingerprint = None;
+
     let graph = mem::take(&mut data.graph);
+    let mut retained = vec![];
 
     for node in graph.nodes() {
         data.graph.add_node();
 
-        for edge in graph.edge_slice(node) {
-            let link = links.next().unwrap();
-            if f(&link) {
-                data.links.push(link);
-                data.graph.add_edge(*edge);
+        for (edge, target) in graph.edge_targets(node) {
+            if f(&data.links.get(edge)) {
+                retained.push(edge);
+                data.graph.add_edge(target);
             }
         }
     }
+
+    data.links = data.links.select(&retained);
 }
 
 pub fn resolve_redirects(data: &mut Data) {
+    data.graph_fingerprint = None;
     // Permutation from input node to input node
     let mut perm_redirect = vec![NodeIdx::NONE; data.pages.len()];
     for node in data.graph.nodes() {
@@ -40,58 +45,85 @@ pub fn resolve_redirects(data: &mut Data) {
         }
     }
 
-    let mut pages = mem::take(&mut data.pages).into_iter();
-    let mut links = mem::take(&mut data.links).into_iter();
     let graph = mem::take(&mut data.graph);
+    let mut retained_nodes = vec![];
+    let mut retained_edges = vec![];
 
     for node in graph.nodes() {
-        let page = pages.next().unwrap();
-        let new_node = perm_retain[node.usize()];
-
-        if new_node == NodeIdx::NONE {
-            // Skip all edges
-            for _ in graph.edge_slice(node) {
-                links.next().unwrap();
-            }
+        if perm_retain[node.usize()] == NodeIdx::NONE {
             continue;
         }
 
-        data.pages.push(page);
+        retained_nodes.push(node);
         data.graph.add_node();
 
-        for edge in graph.edge_slice(node) {
-            let link = links.next().unwrap();
-            let new_edge = perm_retain[perm_redirect[edge.usize()].usize()];
+        for (edge, target) in graph.edge_targets(node) {
+            let new_target = perm_retain[perm_redirect[target.usize()].usize()];
 
-            if new_edge == NodeIdx::NONE {
+            if new_target == NodeIdx::NONE {
                 continue;
             }
 
-            data.links.push(link);
-            data.graph.add_edge(new_edge);
+            retained_edges.push(edge);
+            data.graph.add_edge(new_target);
         }
     }
+
+    data.pages = data.pages.select(&retained_nodes);
+    data.title_index = None;
+    data.links = data.links.select(&retained_edges);
 }
 
+/// If the graph has in-edges, they are swapped with the edges instead of
+/// rebuilding the graph.
 pub fn invert(data: &mut Data) {
-    let links = mem::take(&mut data.links);
+    data.graph_fingerprint = None;
+    if let Some(edges) = data.graph.swap_reverse() {
+        data.links = data.links.select(&edges);
+        return;
+    }
+
     let graph = mem::take(&mut data.graph);
 
     let mut edges = graph
         .edges()
-        .zip(links)
-        .map(|((source, target), link)| (source, target, link))
+        .zip((0..graph.edges.len()).map(EdgeIdx::new))
+        .map(|((source, target), edge)| (source, target, edge))
         .collect::<Vec<_>>();
 
     edges.sort_by_key(|(_, target, _)| *target);
 
+    let mut retained = Vec::with_capacity(edges.len());
     let mut edges = edges.into_iter().peekable();
     for node in graph.nodes() {
         data.graph.add_node();
         while edges.peek().is_some_and(|(_, target, _)| *target <= node) {
-            let (source, _, link) = edges.next().unwrap();
+            let (source, _, edge) = edges.next().unwrap();
             data.graph.add_edge(source);
-            data.links.push(link);
+            retained.push(edge);
+        }
+    }
+
+    data.links = data.links.select(&retained);
+}
+
+/// Sort the edges of every node by target, keeping the links aligned with
+/// their edges.
+pub fn sort_edges(data: &mut Data) {
+    data.graph_fingerprint = None;
+    let graph = mem::take(&mut data.graph);
+    let mut retained = Vec::with_capacity(graph.edges.len());
+
+    for node in graph.nodes() {
+        data.graph.add_node();
+
+        let mut edges = graph.edge_targets(node).collect::<Vec<_>>();
+        edges.sort_by_key(|(_, target)| *target);
+        for (edge, target) in edges {
+            retained.push(edge);
+            data.graph.add_edge(target);
         }
     }
+
+    data.links = data.links.select(&retained);
 }
@@ -0,0 +1,319 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet},
+};
+
+use crate::graph::{EdgeIdx, Graph, NodeIdx};
+
+/// A loop of edges that cost nothing lies on the shortest paths, so they can't
+/// be counted by [`Geodesics::find`]. Contains a node on or after the loop.
+#[derive(Debug, Clone, Copy)]
+pub struct ZeroCostLoop(pub NodeIdx);
+
+/// All shortest paths from a start node to a goal node.
+///
+/// Counts saturate at `u64::MAX`.
+pub struct Geodesics {
+    start: NodeIdx,
+    goal: NodeIdx,
+    cost: u32,
+    /// Every node on a shortest path, ordered so that each node comes after
+    /// all of its predecessors.
+    nodes: Vec<NodeIdx>,
+    /// The predecessors of each node on shortest paths, without duplicates.
+    preds: HashMap<NodeIdx, Vec<NodeIdx>>,
+    /// Number of shortest paths from the start to each node.
+    from_start: HashMap<NodeIdx, u64>,
+    /// Number of shortest paths from each node to the goal.
+    to_goal: HashMap<NodeIdx, u64>,
+}
+
+impl Geodesics {
+    /// Returns `None` if there is no path from start to goal, and an error if
+    /// a loop of free edges lies on the shortest paths. Edges costing
+    /// `u32::MAX` are never taken.
+    pub fn find(
+        graph: &Graph,
+        start: NodeIdx,
+        goal: NodeIdx,
+        cost: impl Fn(NodeIdx, EdgeIdx, NodeIdx) -> u32,
+    ) -> Result<Option<Self>, ZeroCostLoop> {
+        // Find the cost of every node that is no more expensive than the goal.
+        let mut costs = HashMap::<NodeIdx, u32>::new();
+        let mut settled = vec![];
+        costs.insert(start, 0);
+        let mut queue = BinaryHeap::new();
+        queue.push((Reverse(0), start));
+        while let Some((Reverse(curr_cost), curr)) = queue.pop() {
+            if costs.get(&goal).is_some_and(|c| curr_cost > *c) {
+                break; // All shortest paths to the goal are known
+            }
+            if curr_cost > costs[&curr] {
+                continue; // Outdated entry
+            }
+            settled.push(curr);
+
+            for (edge, next) in graph.edge_targets(curr) {
+                let edge_cost = cost(curr, edge, next);
+                if edge_cost == u32::MAX {
+                    continue;
+                }
+                let next_cost = curr_cost.saturating_add(edge_cost);
+                if costs.get(&next).is_none_or(|c| next_cost < *c) {
+                    costs.insert(next, next_cost);
+                    queue.push((Reverse(next_cost), next));
+                }
+            }
+        }
+
+        let Some(&goal_cost) = costs.get(&goal) else {
+            return Ok(None);
+        };
+
+        // Every edge along which the cost increases by exactly its own cost
+        // is part of a shortest path.
+        let mut preds = HashMap::<NodeIdx, Vec<NodeIdx>>::new();
+        for curr in settled {
+            for (edge, next) in graph.edge_targets(curr) {
+                let edge_cost = cost(curr, edge, next);
+                if edge_cost != u32::MAX && costs[&next] == costs[&curr] + edge_cost {
+                    let next_preds = preds.entry(next).or_default();
+                    if !next_preds.contains(&curr) {
+                        next_preds.push(curr);
+                    }
+                }
+            }
+        }
+
+        // Paths leading back to the start are never shortest.
+        preds.remove(&start);
+
+        // Only keep the nodes from which the goal can be reached.
+        let mut on_path = HashSet::from([goal]);
+        let mut stack = vec![goal];
+        while let Some(node) = stack.pop() {
+            for pred in preds.get(&node).into_iter().flatten() {
+                if on_path.insert(*pred) {
+                    stack.push(*pred);
+                }
+            }
+        }
+        preds.retain(|n, _| on_path.contains(n));
+
+        // Order the nodes so that each comes after all of its predecessors.
+        // Nodes on or after a loop of edges that cost nothing never get a
+        // place in this order, so the paths through them can't be counted.
+        // Redirect loops and zero weights both form such loops.
+        let mut succs = HashMap::<NodeIdx, Vec<NodeIdx>>::new();
+        let mut pending = HashMap::<NodeIdx, usize>::new();
+        for (node, node_preds) in &preds {
+            pending.insert(*node, node_preds.len());
+            for pred in node_preds {
+                succs.entry(*pred).or_default().push(*node);
+            }
+        }
+        let mut nodes = vec![];
+        let mut ready = vec![start];
+        while let Some(node) = ready.pop() {
+            nodes.push(node);
+            for succ in succs.get(&node).into_iter().flatten() {
+                let count = pending.get_mut(succ).unwrap();
+                *count -= 1;
+                if *count == 0 {
+                    ready.push(*succ);
+                }
+            }
+        }
+        let ordered = nodes.iter().copied().collect::<HashSet<_>>();
+        if let Some(node) = preds.keys().filter(|n| !ordered.contains(n)).min() {
+            return Err(ZeroCostLoop(*node));
+        }
+
+        let mut from_start = HashMap::from([(start, 1_u64)]);
+        for node in &nodes {
+            let count = preds
+                .get(node)
+                .into_iter()
+                .flatten()
+                .map(|p| from_start[p])
+                .fold(0_u64, u64::saturating_add);
+            from_start.entry(*node).or_insert(count);
+        }
+
+        let mut to_goal = HashMap::from([(goal, 1_u64)]);
+        for node in nodes.iter().rev() {
+            let count = to_goal.get(node).copied().unwrap_or(0);
+            for pred in preds.get(node).into_iter().flatten() {
+                let entry = to_goal.entry(*pred).or_default();
+                *entry = entry.saturating_add(count);
+            }
+        }
+
+        let result = Self {
+            start,
+            goal,
+            cost: goal_cost,
+            nodes,
+            preds,
+            from_start,
+            to_goal,
+        };
+        Ok(Some(result).filter(|r| r.count() > 0))
+    }
+
+    pub fn cost(&self) -> u32 {
+        self.cost
+    }
+
+    /// The number of distinct shortest paths.
+    pub fn count(&self) -> u64 {
+        self.from_start.get(&self.goal).copied().unwrap_or(0)
+    }
+
+    /// The number of shortest paths passing through a node.
+    pub fn through(&self, node: NodeIdx) -> u64 {
+        let from_start = self.from_start.get(&node).copied().unwrap_or(0);
+        let to_goal = self.to_goal.get(&node).copied().unwrap_or(0);
+        from_start.saturating_mul(to_goal)
+    }
+
+    /// The nodes between start and goal, most used first.
+    pub fn intermediates(&self) -> Vec<(NodeIdx, u64)> {
+        let mut result = self
+            .nodes
+            .iter()
+            .filter(|n| **n != self.start && **n != self.goal)
+            .map(|n| (*n, self.through(*n)))
+            .collect::<Vec<_>>();
+        result.sort_by_key(|(n, count)| (Reverse(*count), *n));
+        result
+    }
+
+    /// Up to `limit` of the shortest paths.
+    pub fn paths(&self, limit: usize) -> Vec<Vec<NodeIdx>> {
+        let mut result = vec![];
+        // Paths are built backwards from the goal.
+        let mut stack = vec![vec![self.goal]];
+        while let Some(path) = stack.pop() {
+            if result.len() >= limit {
+                break;
+            }
+
+            let last = *path.last().unwrap();
+            if last == self.start {
+                result.push(path.into_iter().rev().collect());
+                continue;
+            }
+
+            for pred in self.preds.get(&last).into_iter().flatten().rev() {
+                let mut longer = path.clone();
+                longer.push(*pred);
+                stack.push(longer);
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit(_: NodeIdx, _: EdgeIdx, _: NodeIdx) -> u32 {
+        1
+    }
+
+    fn ids(paths: Vec<Vec<NodeIdx>>) -> Vec<Vec<u32>> {
+        paths
+            .into_iter()
+            .map(|path| path.into_iter().map(|n| n.0).collect())
+            .collect()
+    }
+
+    #[test]
+    fn diamond() {
+        // The duplicate edge from 0 to 1 doesn't add a path.
+        let graph = Graph::from_adjacency(&[&[1, 1, 2], &[3], &[3], &[]]);
+        let geodesics = Geodesics::find(&graph, NodeIdx(0), NodeIdx(3), unit)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(geodesics.cost(), 2);
+        assert_eq!(geodesics.count(), 2);
+        assert_eq!(geodesics.through(NodeIdx(1)), 1);
+        assert_eq!(geodesics.through(NodeIdx(3)), 2);
+        assert_eq!(
+            geodesics.intermediates(),
+            [(NodeIdx(1), 1), (NodeIdx(2), 1)]
+        );
+        let mut paths = ids(geodesics.paths(10));
+        paths.sort();
+        assert_eq!(paths, [[0, 1, 3], [0, 2, 3]]);
+        assert_eq!(geodesics.paths(1).len(), 1);
+    }
+
+    #[test]
+    fn chained_diamonds() {
+        // A third, longer branch from 0 to 3 is not shortest.
+        let graph =
+            Graph::from_adjacency(&[&[1, 2, 7], &[3], &[3], &[4, 5], &[6], &[6], &[], &[8], &[3]]);
+        let geodesics = Geodesics::find(&graph, NodeIdx(0), NodeIdx(6), unit)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(geodesics.cost(), 4);
+        assert_eq!(geodesics.count(), 4);
+        assert_eq!(geodesics.through(NodeIdx(3)), 4);
+        assert_eq!(geodesics.through(NodeIdx(7)), 0);
+        assert_eq!(geodesics.intermediates()[0], (NodeIdx(3), 4));
+        assert_eq!(geodesics.paths(10).len(), 4);
+    }
+
+    #[test]
+    fn zero_cost_redirects() {
+        // 1 and 2 are redirects, so leaving them is free.
+        let graph = Graph::from_adjacency(&[&[1, 2], &[3], &[1, 3], &[]]);
+        let cost = |source: NodeIdx, _, _| !matches!(source.0, 1 | 2) as u32;
+        let geodesics = Geodesics::find(&graph, NodeIdx(0), NodeIdx(3), cost)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(geodesics.cost(), 1);
+        assert_eq!(geodesics.count(), 3);
+        assert_eq!(geodesics.through(NodeIdx(1)), 2);
+        let mut paths = ids(geodesics.paths(10));
+        paths.sort();
+        assert_eq!(paths, [vec![0, 1, 3], vec![0, 2, 1, 3], vec![0, 2, 3]]);
+    }
+
+    #[test]
+    fn unreachable_or_blocked() {
+        let graph = Graph::from_adjacency(&[&[1], &[], &[1]]);
+        assert!(Geodesics::find(&graph, NodeIdx(0), NodeIdx(2), unit)
+            .unwrap()
+            .is_none());
+        let blocked = |_, _, _| u32::MAX;
+        assert!(Geodesics::find(&graph, NodeIdx(0), NodeIdx(1), blocked)
+            .unwrap()
+            .is_none());
+
+        let trivial = Geodesics::find(&graph, NodeIdx(0), NodeIdx(0), unit)
+            .unwrap()
+            .unwrap();
+        assert_eq!((trivial.cost(), trivial.count()), (0, 1));
+    }
+
+    #[test]
+    fn zero_cost_loop() {
+        // Leaving 1 or 2 is free, and they link to each other.
+        let graph = Graph::from_adjacency(&[&[1], &[2], &[1, 3], &[]]);
+        let cost = |source: NodeIdx, _, _| !matches!(source.0, 1 | 2) as u32;
+        let result = Geodesics::find(&graph, NodeIdx(0), NodeIdx(3), cost);
+        assert!(matches!(result, Err(ZeroCostLoop(NodeIdx(1)))));
+
+        // A loop off the shortest paths doesn't matter.
+        let graph = Graph::from_adjacency(&[&[1, 3], &[2], &[1], &[]]);
+        let geodesics = Geodesics::find(&graph, NodeIdx(0), NodeIdx(3), cost).unwrap();
+        assert_eq!(geodesics.unwrap().count(), 1);
+    }
+}
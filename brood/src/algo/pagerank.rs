@@ -0,0 +1,121 @@
+use std::{convert::Infallible, mem};
+
+use crate::{
+    graph::{Graph, NodeIdx},
+    util,
+};
+
+/// Compute the PageRank of every node by power iteration.
+///
+/// A random surfer follows a random edge with probability `damping`, and
+/// otherwise jumps to a random node chosen according to `teleport`, which must
+/// sum to 1. Surfers on nodes without edges always jump. This includes nodes
+/// whose edges were all filtered out.
+///
+/// Iterates until the ranks change by less than `tolerance` in total, or until
+/// `max_iterations` is reached. Returns the ranks, which sum to 1, the number
+/// of iterations, and whether the ranks converged. Requires the in-edges, see
+/// [`Graph::build_reverse`].
+pub fn pagerank(
+    graph: &Graph,
+    teleport: &[f64],
+    damping: f64,
+    tolerance: f64,
+    max_iterations: usize,
+    jobs: usize,
+) -> (Vec<f64>, usize, bool) {
+    let len = graph.nodes.len();
+    let outdegrees = graph
+        .nodes()
+        .map(|n| graph.edge_range(n).len())
+        .collect::<Vec<_>>();
+    let chunk = len.div_ceil(jobs.max(1)).max(1);
+
+    let mut ranks = teleport.to_vec();
+    let mut next = vec![0.0; len];
+    // What each node passes on along every one of its edges.
+    let mut shares = vec![0.0; len];
+
+    for iteration in 1..=max_iterations {
+        let mut dangling = 0.0;
+        for ((share, rank), outdegree) in shares.iter_mut().zip(&ranks).zip(&outdegrees) {
+            if *outdegree == 0 {
+                dangling += rank;
+                *share = 0.0;
+            } else {
+                *share = rank / *outdegree as f64;
+            }
+        }
+
+        // Every job computes the new ranks of one chunk of nodes. The changes
+        // are summed in order so that the result doesn't depend on timing.
+        let mut changes = vec![0.0; len.div_ceil(chunk)];
+        let Ok(()) = util::parallel(
+            jobs,
+            changes.len(),
+            || (),
+            |(), i| {
+                let nodes = i * chunk..((i + 1) * chunk).min(len);
+                let mut change = 0.0;
+                let mut new_ranks = Vec::with_capacity(nodes.len());
+                for node in nodes.map(NodeIdx::new) {
+                    let incoming = graph
+                        .in_edges(node)
+                        .map(|(_, source)| shares[source.usize()])
+                        .sum::<f64>();
+                    let jump = teleport[node.usize()];
+                    let new = (1.0 - damping) * jump + damping * (incoming + dangling * jump);
+                    change += (new - ranks[node.usize()]).abs();
+                    new_ranks.push(new);
+                }
+                (new_ranks, change)
+            },
+            |i, (new_ranks, chunk_change)| -> Result<(), Infallible> {
+                next[i * chunk..i * chunk + new_ranks.len()].copy_from_slice(&new_ranks);
+                changes[i] = chunk_change;
+                Ok(())
+            },
+        );
+        let change = changes.iter().sum::<f64>();
+
+        mem::swap(&mut ranks, &mut next);
+        if change < tolerance {
+            return (ranks, iteration, true);
+        }
+    }
+
+    (ranks, max_iterations, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranks_sum_to_one() {
+        // Node 3 has no edges, so its surfers always jump.
+        let mut graph = Graph::from_adjacency(&[&[1, 2], &[2], &[0], &[]]);
+        graph.build_reverse();
+        let (ranks, _, converged) = pagerank(&graph, &[0.25; 4], 0.85, 1e-12, 1000, 2);
+        assert!(converged);
+        assert!((ranks.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        assert!(ranks[2] > ranks[1]);
+        assert!(ranks[3] < ranks[1]);
+    }
+
+    #[test]
+    fn converged_on_last_iteration() {
+        // The uniform ranks of a cycle are already stable.
+        let mut graph = Graph::from_adjacency(&[&[1], &[2], &[0]]);
+        graph.build_reverse();
+        let (_, iterations, converged) = pagerank(&graph, &[1.0 / 3.0; 3], 0.85, 1e-9, 1, 1);
+        assert_eq!(iterations, 1);
+        assert!(converged);
+
+        let mut graph = Graph::from_adjacency(&[&[1, 2], &[2], &[0]]);
+        graph.build_reverse();
+        let (_, iterations, converged) = pagerank(&graph, &[1.0 / 3.0; 3], 0.85, 1e-9, 1, 1);
+        assert_eq!(iterations, 1);
+        assert!(!converged);
+    }
+}
@@ -0,0 +1,197 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashSet},
+};
+
+use crate::graph::{EdgeIdx, Graph, NodeIdx};
+
+use super::Dijkstra;
+
+/// The cost of following a path, taking the cheapest edge between each pair of
+/// consecutive nodes.
+fn path_cost(
+    graph: &Graph,
+    path: &[NodeIdx],
+    cost: &impl Fn(NodeIdx, EdgeIdx, NodeIdx) -> u32,
+) -> u32 {
+    path.windows(2)
+        .map(|pair| {
+            graph
+                .edge_targets(pair[0])
+                .filter(|(_, target)| *target == pair[1])
+                .map(|(edge, target)| cost(pair[0], edge, target))
+                .min()
+                .unwrap_or(u32::MAX)
+        })
+        .fold(0, u32::saturating_add)
+}
+
+/// Find up to `k` shortest paths from start to goal that don't visit any node
+/// twice, cheapest first.
+///
+/// This is Yen's algorithm: every further path branches off one of the
+/// previous paths at some node, and continues along the shortest path from
+/// there that avoids the branches already taken.
+pub fn k_shortest_paths(
+    graph: &Graph,
+    start: NodeIdx,
+    goal: NodeIdx,
+    k: usize,
+    cost: impl Fn(NodeIdx, EdgeIdx, NodeIdx) -> u32,
+) -> Vec<(u32, Vec<NodeIdx>)> {
+    let mut dijkstra = Dijkstra::new(graph);
+    let mut shortest = |from: NodeIdx,
+                        removed_edges: &HashSet<(NodeIdx, NodeIdx)>,
+                        removed_nodes: &HashSet<NodeIdx>| {
+        dijkstra.run(
+            from,
+            |node| node == goal,
+            |source, edge, target| {
+                if removed_nodes.contains(&target) || removed_edges.contains(&(source, target)) {
+                    u32::MAX
+                } else {
+                    cost(source, edge, target)
+                }
+            },
+        );
+        if dijkstra.cost(goal) == u32::MAX {
+            None
+        } else {
+            Some(dijkstra.path(goal))
+        }
+    };
+
+    let mut result = vec![];
+    if k == 0 {
+        return result;
+    }
+    let Some(first) = shortest(start, &HashSet::new(), &HashSet::new()) else {
+        return result;
+    };
+    let mut seen = HashSet::from([first.clone()]);
+    result.push((path_cost(graph, &first, &cost), first));
+
+    let mut candidates = BinaryHeap::new();
+    while result.len() < k {
+        let (_, prev) = result.last().unwrap();
+        for i in 0..prev.len() - 1 {
+            let spur = prev[i];
+            let root = &prev[..=i];
+
+            // Don't take the same branch as any previous path with this root,
+            // and don't return to the root.
+            let removed_edges = result
+                .iter()
+                .filter(|(_, path)| path.len() > i + 1 && path[..=i] == *root)
+                .map(|(_, path)| (path[i], path[i + 1]))
+                .collect::<HashSet<_>>();
+            let removed_nodes = root[..i].iter().copied().collect::<HashSet<_>>();
+
+            let Some(spur_path) = shortest(spur, &removed_edges, &removed_nodes) else {
+                continue;
+            };
+
+            let mut path = root[..i].to_vec();
+            path.extend(spur_path);
+            if seen.insert(path.clone()) {
+                candidates.push(Reverse((path_cost(graph, &path, &cost), path)));
+            }
+        }
+
+        match candidates.pop() {
+            Some(Reverse(candidate)) => result.push(candidate),
+            None => break,
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph() -> Graph {
+        // The edge from 4 back to 2 only leads to loops.
+        Graph::from_adjacency(&[&[1, 2], &[2, 3], &[3, 4], &[4, 5], &[5, 2], &[]])
+    }
+
+    fn unit(_: NodeIdx, _: EdgeIdx, _: NodeIdx) -> u32 {
+        1
+    }
+
+    /// Every loopless path from the node at the end of `path` to the goal.
+    fn all_paths(
+        graph: &Graph,
+        path: &mut Vec<NodeIdx>,
+        goal: NodeIdx,
+        out: &mut Vec<Vec<NodeIdx>>,
+    ) {
+        let curr = *path.last().unwrap();
+        if curr == goal {
+            out.push(path.clone());
+            return;
+        }
+        for next in graph.targets(curr) {
+            if !path.contains(&next) {
+                path.push(next);
+                all_paths(graph, path, goal, out);
+                path.pop();
+            }
+        }
+    }
+
+    #[test]
+    fn finds_all_loopless_paths_in_order() {
+        let graph = graph();
+        let (start, goal) = (NodeIdx(0), NodeIdx(5));
+        let paths = k_shortest_paths(&graph, start, goal, 100, unit);
+
+        let mut expected = vec![];
+        all_paths(&graph, &mut vec![start], goal, &mut expected);
+        assert_eq!(paths.len(), expected.len());
+
+        let mut seen = HashSet::new();
+        for (i, (cost, path)) in paths.iter().enumerate() {
+            assert!(expected.contains(path), "unexpected path {path:?}");
+            assert!(seen.insert(path), "duplicate path {path:?}");
+            assert_eq!(*cost as usize, path.len() - 1);
+            if i > 0 {
+                assert!(paths[i - 1].0 <= *cost);
+            }
+        }
+    }
+
+    #[test]
+    fn takes_at_most_k() {
+        let graph = graph();
+        let paths = k_shortest_paths(&graph, NodeIdx(0), NodeIdx(5), 4, unit);
+        let costs = paths.iter().map(|(cost, _)| *cost).collect::<Vec<_>>();
+        assert_eq!(costs, [3, 3, 3, 4]);
+
+        assert!(k_shortest_paths(&graph, NodeIdx(0), NodeIdx(5), 0, unit).is_empty());
+        assert!(k_shortest_paths(&graph, NodeIdx(5), NodeIdx(0), 3, unit).is_empty());
+    }
+
+    #[test]
+    fn respects_costs() {
+        // Leaving 1 is expensive, and the edge from 2 to 4 is blocked.
+        let graph = graph();
+        let cost = |source: NodeIdx, _, target: NodeIdx| match (source.0, target.0) {
+            (1, _) => 10,
+            (2, 4) => u32::MAX,
+            _ => 1,
+        };
+        let paths = k_shortest_paths(&graph, NodeIdx(0), NodeIdx(5), 3, cost);
+        let expected: [(u32, &[u32]); 3] = [
+            (3, &[0, 2, 3, 5]),
+            (4, &[0, 2, 3, 4, 5]),
+            (12, &[0, 1, 3, 5]),
+        ];
+        for ((cost, path), (expected_cost, expected_path)) in paths.iter().zip(expected) {
+            assert_eq!(*cost, expected_cost);
+            assert_eq!(path.iter().map(|n| n.0).collect::<Vec<_>>(), expected_path);
+        }
+        assert_eq!(paths.len(), 3);
+    }
+}
@@ -1,7 +1,20 @@
+pub mod bow_tie;
+pub mod browse;
+pub mod components;
+pub mod distances;
 pub mod export;
+pub mod info;
 pub mod ingest;
+pub mod landmarks;
 pub mod longest_path;
+pub mod pagerank;
 pub mod path;
+pub mod path_batch;
 pub mod pg;
+pub mod repl;
+pub mod serve;
+pub mod set_path;
 pub mod show;
 pub mod stats;
+pub mod titles;
+pub mod unlinked;
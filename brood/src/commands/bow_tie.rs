@@ -0,0 +1,132 @@
+use serde_json::{json, Value};
+use thousands::Separable;
+
+use crate::{
+    algo::{self, Components, Region},
+    data::Data,
+    error::Result,
+    output::{self, Csv, Format},
+    util::{self, TitleNormalizer},
+};
+
+/// Sort every article into a region of the bow-tie around the largest strongly
+/// connected component.
+///
+/// The core can reach all of out and be reached from all of in. Tendrils hang
+/// off in or out without touching the core, tubes lead from in to out around
+/// it. As CSV, one row is printed per region, or one row per given title if
+/// there are any.
+#[derive(Debug, clap::Parser)]
+pub struct Cmd {
+    /// Show which region these articles belong to.
+    titles: Vec<String>,
+
+    /// How many titles of each region to show.
+    #[arg(long, short, default_value_t = 5)]
+    samples: usize,
+}
+
+impl Cmd {
+    pub fn run(self, data: &Data, format: Format) -> Result<()> {
+        let normalizer = TitleNormalizer::new();
+
+        eprintln!(">> Resolve articles");
+        let nodes = self
+            .titles
+            .iter()
+            .map(|title| util::resolve_title(&normalizer, data, title))
+            .collect::<Result<Vec<_>>>()?;
+
+        eprintln!(">> Find components");
+        let components = Components::strong(&data.graph);
+
+        eprintln!(">> Find regions");
+        let regions = algo::bow_tie(&data.graph, &components);
+
+        let mut counts = [0_usize; Region::ALL.len()];
+        let mut samples = vec![vec![]; Region::ALL.len()];
+        for node in data.graph.nodes() {
+            let region = regions[node.usize()] as usize;
+            counts[region] += 1;
+            if samples[region].len() < self.samples {
+                samples[region].push(node);
+            }
+        }
+
+        match format {
+            Format::Text => {
+                let total = data.pages.len().max(1) as f64;
+                for region in Region::ALL {
+                    let count = counts[region as usize];
+                    println!(
+                        "{:>12}: {:>11} ({:5.1}%)",
+                        region.name(),
+                        count.separate_with_underscores(),
+                        count as f64 / total * 100.0
+                    );
+                }
+
+                for region in Region::ALL {
+                    if samples[region as usize].is_empty() {
+                        continue;
+                    }
+                    println!();
+                    println!("Samples from {}:", region.name());
+                    for node in &samples[region as usize] {
+                        println!("{}", util::fmt_page(&data.pages.get(*node)));
+                    }
+                }
+
+                if !nodes.is_empty() {
+                    println!();
+                    for node in &nodes {
+                        println!(
+                            "{} is in {}",
+                            data.pages.title(*node),
+                            regions[node.usize()].name()
+                        );
+                    }
+                }
+            }
+            Format::Json => {
+                let summary = Region::ALL
+                    .into_iter()
+                    .map(|region| {
+                        json!({
+                            "region": region.name(),
+                            "count": counts[region as usize],
+                            "samples": output::pages(data, samples[region as usize].iter().copied()),
+                        })
+                    })
+                    .collect::<Value>();
+                let titles = nodes
+                    .iter()
+                    .map(|node| {
+                        json!({
+                            "page": output::page(data, *node),
+                            "region": regions[node.usize()].name(),
+                        })
+                    })
+                    .collect::<Value>();
+                output::print_json(&json!({ "regions": summary, "titles": titles }))?;
+            }
+            Format::Csv if nodes.is_empty() => {
+                let mut csv = Csv::new(&["region", "count"])?;
+                for region in Region::ALL {
+                    csv.row(&[&region.name(), &counts[region as usize]])?;
+                }
+                csv.finish()?;
+            }
+            Format::Csv => {
+                let mut csv = Csv::new(&["title", "redirect", "region"])?;
+                for node in nodes {
+                    let page = data.pages.get(node);
+                    csv.row(&[&page.title, &page.redirect, &regions[node.usize()].name()])?;
+                }
+                csv.finish()?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,372 @@
+use std::collections::{BTreeMap, HashSet};
+
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout, Rect},
+    style::{Style, Stylize},
+    text::Line,
+    widgets::{Block, List, ListState, Paragraph},
+    DefaultTerminal, Frame,
+};
+use thousands::Separable;
+
+use crate::{
+    commands::path,
+    data::{Data, Link},
+    error::Result,
+    graph::{EdgeIdx, NodeIdx},
+    util::{self, TitleNormalizer},
+};
+
+const HELP: &str =
+    "tab: list  enter: jump  backspace: back  /: go to  s/g: mark start/goal  p: path  q: quit";
+
+/// A neighbour of the current page, shown in one of the lists.
+struct Entry {
+    node: NodeIdx,
+    /// The first link between the current page and the neighbour.
+    link: Option<Link>,
+}
+
+impl Entry {
+    fn fmt(&self, data: &Data) -> String {
+        let mut result = util::fmt_page(&data.pages.get(self.node));
+        if let Some(link) = self.link {
+            if link.in_parens() {
+                result.push_str(" (parens)");
+            }
+            if link.in_structure() {
+                result.push_str(" (structure)");
+            }
+        }
+        result
+    }
+}
+
+struct Tab {
+    name: &'static str,
+    entries: Vec<Entry>,
+    state: ListState,
+}
+
+impl Tab {
+    fn new(name: &'static str, entries: Vec<Entry>) -> Self {
+        let mut state = ListState::default();
+        if !entries.is_empty() {
+            state.select_first();
+        }
+        Self {
+            name,
+            entries,
+            state,
+        }
+    }
+
+    fn selected(&self) -> Option<NodeIdx> {
+        let i = self.state.selected()?;
+        self.entries.get(i).map(|e| e.node)
+    }
+}
+
+struct Browser<'a> {
+    data: &'a Data,
+    normalizer: TitleNormalizer,
+    node: NodeIdx,
+    back: Vec<NodeIdx>,
+    tabs: Vec<Tab>,
+    tab: usize,
+    start: Option<NodeIdx>,
+    goal: Option<NodeIdx>,
+    path: Option<(u32, Vec<NodeIdx>)>,
+    /// Title typed after pressing `/`.
+    input: Option<String>,
+    message: String,
+    quit: bool,
+}
+
+impl<'a> Browser<'a> {
+    fn new(data: &'a Data, node: NodeIdx) -> Self {
+        let mut browser = Self {
+            data,
+            normalizer: TitleNormalizer::new(),
+            node,
+            back: vec![],
+            tabs: vec![],
+            tab: 0,
+            start: None,
+            goal: None,
+            path: None,
+            input: None,
+            message: HELP.to_string(),
+            quit: false,
+        };
+        browser.load_tabs();
+        browser
+    }
+
+    fn load_tabs(&mut self) {
+        let data = self.data;
+
+        // Keyed by neighbour, keeping only the first link to or from it.
+        let mut outlinks = BTreeMap::new();
+        for (edge, target) in data.graph.edge_targets(self.node) {
+            outlinks.entry(target).or_insert(edge);
+        }
+        let mut inlinks = BTreeMap::new();
+        for (edge, source) in data.graph.in_edges(self.node) {
+            inlinks.entry(source).or_insert(edge);
+        }
+
+        let twins = outlinks
+            .keys()
+            .filter(|n| inlinks.contains_key(n))
+            .copied()
+            .collect::<HashSet<_>>();
+
+        let entries = |links: &BTreeMap<NodeIdx, EdgeIdx>, twins_only: bool| {
+            let mut entries = links
+                .iter()
+                .filter(|(n, _)| twins.contains(n) == twins_only)
+                .map(|(&node, &edge)| Entry {
+                    node,
+                    link: Some(data.links.get(edge)),
+                })
+                .collect::<Vec<_>>();
+            entries.sort_by_key(|e| data.pages.title(e.node));
+            entries
+        };
+
+        self.tabs = vec![
+            Tab::new("Twins", entries(&outlinks, true)),
+            Tab::new("Only outlinks", entries(&outlinks, false)),
+            Tab::new("Only inlinks", entries(&inlinks, false)),
+        ];
+
+        if let Some((_, path)) = &self.path {
+            let entries = path
+                .iter()
+                .map(|&node| Entry { node, link: None })
+                .collect();
+            self.tabs.push(Tab::new("Path", entries));
+        }
+
+        self.tab = self.tab.min(self.tabs.len() - 1);
+    }
+
+    fn jump(&mut self, node: NodeIdx) {
+        if node != self.node {
+            self.back.push(self.node);
+            self.node = node;
+            self.load_tabs();
+        }
+    }
+
+    fn go_back(&mut self) {
+        if let Some(node) = self.back.pop() {
+            self.node = node;
+            self.load_tabs();
+        }
+    }
+
+    fn go_to(&mut self, title: &str) {
+        match util::find_title(&self.normalizer, self.data, title) {
+            Some(node) => self.jump(node),
+            None => {
+                self.message = util::title_not_found(&self.normalizer, self.data, title)
+                    .to_string()
+                    .replace('\n', " ");
+            }
+        }
+    }
+
+    fn search_path(&mut self) {
+        let (Some(start), Some(goal)) = (self.start, self.goal) else {
+            self.message = "Mark a start with s and a goal with g first".to_string();
+            return;
+        };
+
+        let start = util::resolve_redirects(self.data, start);
+        let goal = util::resolve_redirects(self.data, goal);
+        self.path = path::shortest_path(self.data, start, goal);
+        self.message = match &self.path {
+            Some((cost, path)) => format!("Path found (cost {cost}, length {})", path.len()),
+            None => "No path found".to_string(),
+        };
+
+        self.load_tabs();
+        if self.path.is_some() {
+            self.tab = self.tabs.len() - 1;
+        }
+    }
+
+    fn handle_input(&mut self, code: KeyCode) {
+        let Some(input) = &mut self.input else {
+            return;
+        };
+        match code {
+            KeyCode::Char(c) => input.push(c),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Esc => self.input = None,
+            KeyCode::Enter => {
+                let title = self.input.take().unwrap_or_default();
+                self.go_to(&title);
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_key(&mut self, code: KeyCode) {
+        if self.input.is_some() {
+            self.handle_input(code);
+            return;
+        }
+
+        self.message = HELP.to_string();
+        let tabs = self.tabs.len();
+        let state = &mut self.tabs[self.tab].state;
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Tab | KeyCode::Right | KeyCode::Char('l') => self.tab = (self.tab + 1) % tabs,
+            KeyCode::BackTab | KeyCode::Left | KeyCode::Char('h') => {
+                self.tab = (self.tab + tabs - 1) % tabs;
+            }
+            KeyCode::Down | KeyCode::Char('j') => state.select_next(),
+            KeyCode::Up | KeyCode::Char('k') => state.select_previous(),
+            KeyCode::PageDown => state.scroll_down_by(20),
+            KeyCode::PageUp => state.scroll_up_by(20),
+            KeyCode::Home => state.select_first(),
+            KeyCode::End => state.select_last(),
+            KeyCode::Enter => {
+                if let Some(node) = self.tabs[self.tab].selected() {
+                    self.jump(node);
+                }
+            }
+            KeyCode::Backspace | KeyCode::Char('b') => self.go_back(),
+            KeyCode::Char('/') => self.input = Some(String::new()),
+            KeyCode::Char('s') => {
+                self.start = Some(self.node);
+                self.message = "Marked as start".to_string();
+            }
+            KeyCode::Char('g') => {
+                self.goal = Some(self.node);
+                self.message = "Marked as goal".to_string();
+            }
+            KeyCode::Char('p') => self.search_path(),
+            _ => {}
+        }
+    }
+
+    fn draw_page(&self, frame: &mut Frame, area: Rect) {
+        let data = self.data;
+        let page = data.pages.get(self.node);
+        let mark = |node: Option<NodeIdx>| match node {
+            Some(node) => data.pages.title(node).to_string(),
+            None => "-".to_string(),
+        };
+        let redirect = match data.redirect_target(self.node) {
+            Some(target) => format!("yes, to {}", data.pages.title(target)),
+            None => "no".to_string(),
+        };
+        let outlinks = data.graph.edge_range(self.node).len();
+        let twins = self.tabs[0].entries.len();
+
+        let lines = vec![
+            Line::from(format!(
+                "Title (norm): {}",
+                self.normalizer.normalize(page.title)
+            )),
+            Line::from(format!("    Redirect: {redirect}")),
+            Line::from(format!(
+                "          ID: {}    Length: {}",
+                page.id,
+                page.length.separate_with_underscores()
+            )),
+            Line::from(format!(
+                "       Links: {} out, {} unique out, {} unique in, {} twins",
+                outlinks.separate_with_underscores(),
+                (twins + self.tabs[1].entries.len()).separate_with_underscores(),
+                (twins + self.tabs[2].entries.len()).separate_with_underscores(),
+                twins.separate_with_underscores(),
+            )),
+            Line::from(format!(
+                "  Start/goal: {} / {}",
+                mark(self.start),
+                mark(self.goal)
+            )),
+        ];
+
+        let block = Block::bordered().title(page.title.bold());
+        frame.render_widget(Paragraph::new(lines).block(block), area);
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [page, lists, status] = Layout::vertical([
+            Constraint::Length(7),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        self.draw_page(frame, page);
+
+        let columns = Layout::horizontal(vec![Constraint::Fill(1); self.tabs.len()]).split(lists);
+        for (i, (tab, area)) in self.tabs.iter_mut().zip(columns.iter()).enumerate() {
+            let title = format!(" {} ({}) ", tab.name, tab.entries.len());
+            let mut block = Block::bordered().title(title);
+            if i == self.tab {
+                block = block.border_style(Style::new().yellow());
+            }
+            let items = tab.entries.iter().map(|e| e.fmt(self.data));
+            let list = List::new(items)
+                .block(block)
+                .highlight_style(Style::new().reversed());
+            frame.render_stateful_widget(list, *area, &mut tab.state);
+        }
+
+        let status_line = match &self.input {
+            Some(input) => format!("Go to: {input}"),
+            None => self.message.clone(),
+        };
+        frame.render_widget(Paragraph::new(status_line), status);
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<()> {
+        while !self.quit {
+            terminal.draw(|frame| self.draw(frame))?;
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press {
+                    self.handle_key(key.code);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Explore articles and their links interactively.
+///
+/// Shows the twins, outlink-only and inlink-only neighbours of an article
+/// together with the flags of the first link between them. Neighbours can be
+/// visited, and articles can be marked as start and goal of a path search.
+/// Without in-edges in the datafile, they are collected on startup.
+#[derive(Debug, clap::Parser)]
+pub struct Cmd {
+    title: String,
+}
+
+impl Cmd {
+    pub fn run(self, data: &Data) -> Result<()> {
+        let normalizer = TitleNormalizer::new();
+
+        eprintln!(">> Locate article");
+        let node = util::locate_title(&normalizer, data, &self.title)?;
+
+        let mut browser = Browser::new(data, node);
+        let mut terminal = ratatui::init();
+        let result = browser.run(&mut terminal);
+        ratatui::restore();
+        result
+    }
+}
@@ -0,0 +1,221 @@
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{BufWriter, Write},
+    path::PathBuf,
+};
+
+use serde_json::{json, Value};
+use thousands::Separable;
+
+use crate::{
+    algo::Components,
+    data::Data,
+    error::Result,
+    graph::NodeIdx,
+    output::{self, Csv, Format},
+    util::{self, TitleNormalizer},
+};
+
+/// Find the strongly connected components, in which every article can reach
+/// every other article.
+///
+/// Components are numbered so that links between components always lead to a
+/// higher number. With `--weak`, the weakly connected components are found
+/// instead, in which articles only need to be linked in either direction. As
+/// CSV, one row is printed per component size with the
+/// number of components of that size, or one row per given title if there are
+/// any.
+#[derive(Debug, clap::Parser)]
+pub struct Cmd {
+    /// Show which component these articles belong to.
+    titles: Vec<String>,
+
+    /// Ignore the direction of links.
+    #[arg(long, short)]
+    weak: bool,
+
+    /// List the members of components with at least 2 and at most this many
+    /// articles.
+    #[arg(long, short, default_value_t = 3)]
+    small: usize,
+
+    /// How many small components to list.
+    #[arg(long, short, default_value_t = 10)]
+    limit: usize,
+
+    /// Export the links between components as CSV.
+    ///
+    /// Every row contains the source and target component with their sizes,
+    /// and the number of links between them.
+    #[arg(long, value_name = "FILE", conflicts_with = "weak")]
+    condensation: Option<PathBuf>,
+}
+
+impl Cmd {
+    fn export_condensation(&self, data: &Data, components: &Components) -> Result<()> {
+        let Some(file) = &self.condensation else {
+            return Ok(());
+        };
+
+        eprintln!(">> Export condensation");
+        let mut edges = components
+            .condensation(&data.graph)
+            .into_iter()
+            .collect::<Vec<_>>();
+        edges.sort_unstable();
+
+        let mut out = BufWriter::new(File::create(file)?);
+        output::write_csv_row(
+            &mut out,
+            &[
+                &"source",
+                &"source_size",
+                &"target",
+                &"target_size",
+                &"links",
+            ],
+        )?;
+        for ((source, target), links) in edges {
+            output::write_csv_row(
+                &mut out,
+                &[
+                    &source,
+                    &components.size(source),
+                    &target,
+                    &components.size(target),
+                    &links,
+                ],
+            )?;
+        }
+        out.flush()?;
+        Ok(())
+    }
+
+    pub fn run(self, data: &Data, format: Format) -> Result<()> {
+        let normalizer = TitleNormalizer::new();
+
+        eprintln!(">> Resolve articles");
+        let nodes = self
+            .titles
+            .iter()
+            .map(|title| util::resolve_title(&normalizer, data, title))
+            .collect::<Result<Vec<_>>>()?;
+
+        eprintln!(">> Find components");
+        let components = if self.weak {
+            Components::weak(&data.graph)
+        } else {
+            Components::strong(&data.graph)
+        };
+
+        self.export_condensation(data, &components)?;
+
+        // Size of every component mapped to the number of such components.
+        let mut distribution = BTreeMap::<usize, usize>::new();
+        for size in components.sizes() {
+            *distribution.entry(*size).or_default() += 1;
+        }
+        let largest = distribution.keys().next_back().copied().unwrap_or(0);
+
+        let mut small = components
+            .members(|c| (2..=self.small).contains(&components.size(c)))
+            .into_iter()
+            .collect::<Vec<_>>();
+        small.sort_unstable_by_key(|(c, members)| (members.len(), *c));
+        small.truncate(self.limit);
+
+        match format {
+            Format::Text => {
+                println!(
+                    "{} components, the largest with {} of {} articles",
+                    components.count().separate_with_underscores(),
+                    largest.separate_with_underscores(),
+                    data.pages.len().separate_with_underscores()
+                );
+
+                println!();
+                println!("Size distribution:");
+                for (size, count) in &distribution {
+                    println!(
+                        "{:>11} articles: {:>11} components",
+                        size.separate_with_underscores(),
+                        count.separate_with_underscores()
+                    );
+                }
+
+                if !small.is_empty() {
+                    println!();
+                    println!("Small components:");
+                    for (c, members) in &small {
+                        println!();
+                        println!("Component {c}:");
+                        for node in members {
+                            println!("{}", util::fmt_page(&data.pages.get(*node)));
+                        }
+                    }
+                }
+
+                if !nodes.is_empty() {
+                    println!();
+                    for node in &nodes {
+                        let c = components.of(*node);
+                        println!(
+                            "{} is in component {c} with {} articles",
+                            data.pages.title(*node),
+                            components.size(c).separate_with_underscores()
+                        );
+                    }
+                }
+            }
+            Format::Json => {
+                let distribution = distribution
+                    .iter()
+                    .map(|(size, count)| json!({ "size": size, "count": count }))
+                    .collect::<Value>();
+                let small = small
+                    .into_iter()
+                    .map(|(c, members)| json!({ "component": c, "members": output::pages(data, members) }))
+                    .collect::<Value>();
+                let titles = nodes
+                    .iter()
+                    .map(|node| component_json(data, &components, *node))
+                    .collect::<Value>();
+                output::print_json(&json!({
+                    "components": components.count(),
+                    "largest": largest,
+                    "distribution": distribution,
+                    "small": small,
+                    "titles": titles,
+                }))?;
+            }
+            Format::Csv if nodes.is_empty() => {
+                let mut csv = Csv::new(&["size", "count"])?;
+                for (size, count) in &distribution {
+                    csv.row(&[size, count])?;
+                }
+                csv.finish()?;
+            }
+            Format::Csv => {
+                let mut csv = Csv::new(&["title", "redirect", "component", "size"])?;
+                for node in nodes {
+                    let page = data.pages.get(node);
+                    let c = components.of(node);
+                    csv.row(&[&page.title, &page.redirect, &c, &components.size(c)])?;
+                }
+                csv.finish()?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn component_json(data: &Data, components: &Components, node: NodeIdx) -> Value {
+    let c = components.of(node);
+    json!({
+        "page": output::page(data, node),
+        "component": c,
+        "size": components.size(c),
+    })
+}
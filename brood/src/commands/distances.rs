@@ -0,0 +1,161 @@
+use std::{cell::RefCell, collections::HashSet, fmt::Display, path::PathBuf};
+
+use indicatif::ProgressBar;
+use serde_json::json;
+
+use crate::{
+    algo::Dijkstra,
+    data::Data,
+    error::Result,
+    graph::NodeIdx,
+    output::{self, Csv, Format},
+    util::{self, TitleNormalizer},
+};
+
+/// Find the shortest distances between every pair of articles in a list.
+///
+/// The file contains one title per line. Distances are directed, so the
+/// distance from the first to the second article can differ from the distance
+/// back. The eccentricity of an article is its largest distance to any other
+/// article in the list, and missing if one of them is unreachable.
+///
+/// As CSV, every row contains the distances from one article, with one column
+/// per article it leads to.
+#[derive(Debug, clap::Parser)]
+pub struct Cmd {
+    titles: PathBuf,
+
+    #[command(flatten)]
+    jobs: util::Jobs,
+}
+
+/// The distances from one article to every article of the list.
+fn distances(
+    dijkstra: &mut Dijkstra<'_>,
+    data: &Data,
+    nodes: &[NodeIdx],
+    i: usize,
+) -> Vec<Option<u32>> {
+    // Every article of the list must be reached before the search can stop.
+    let remaining = RefCell::new(nodes.iter().copied().collect::<HashSet<_>>());
+    dijkstra.run(
+        nodes[i],
+        |node| {
+            let mut remaining = remaining.borrow_mut();
+            remaining.remove(&node);
+            remaining.is_empty()
+        },
+        |source, _edge, _target| !data.pages.redirect(source) as u32,
+    );
+
+    nodes
+        .iter()
+        .map(|n| Some(dijkstra.cost(*n)).filter(|c| *c < u32::MAX))
+        .collect()
+}
+
+fn eccentricity(row: &[Option<u32>]) -> Option<u32> {
+    row.iter().try_fold(0, |max, dist| dist.map(|d| max.max(d)))
+}
+
+fn fmt_distance(distance: Option<u32>) -> String {
+    match distance {
+        Some(distance) => distance.to_string(),
+        None => String::new(),
+    }
+}
+
+impl Cmd {
+    pub fn run(self, data: &Data, format: Format) -> Result<()> {
+        let normalizer = TitleNormalizer::new();
+
+        eprintln!(">> Resolve articles");
+        let nodes = util::resolve_title_file(&normalizer, data, &self.titles)?;
+
+        eprintln!(">> Find distances");
+        let bar = ProgressBar::new(nodes.len() as u64);
+        let mut rows = vec![vec![]; nodes.len()];
+        util::parallel(
+            self.jobs.get(),
+            nodes.len(),
+            || Dijkstra::new(&data.graph),
+            |dijkstra, i| distances(dijkstra, data, &nodes, i),
+            |i, row| -> Result<()> {
+                rows[i] = row;
+                bar.inc(1);
+                Ok(())
+            },
+        )?;
+        bar.finish_and_clear();
+
+        let eccentricities = rows.iter().map(|r| eccentricity(r)).collect::<Vec<_>>();
+
+        match format {
+            Format::Text => {
+                println!("Articles:");
+                for (i, node) in nodes.iter().enumerate() {
+                    let ecc = match eccentricities[i] {
+                        Some(ecc) => ecc.to_string(),
+                        None => "unreachable".to_string(),
+                    };
+                    println!(
+                        "{:3}. {} (eccentricity {ecc})",
+                        i + 1,
+                        util::fmt_page(&data.pages.get(*node))
+                    );
+                }
+
+                println!();
+                println!("Distances (row to column, - if unreachable):");
+                let width = rows
+                    .iter()
+                    .flatten()
+                    .flatten()
+                    .map(|d| d.to_string().len())
+                    .chain([(nodes.len()).to_string().len()])
+                    .max()
+                    .unwrap_or(1);
+                let header = (1..=nodes.len())
+                    .map(|i| format!("{i:>width$}"))
+                    .collect::<Vec<_>>();
+                println!("{:>4} {}", "", header.join(" "));
+                for (i, row) in rows.iter().enumerate() {
+                    let cells = row
+                        .iter()
+                        .map(|d| match d {
+                            Some(d) => format!("{d:>width$}"),
+                            None => format!("{:>width$}", "-"),
+                        })
+                        .collect::<Vec<_>>();
+                    println!("{:>3}. {}", i + 1, cells.join(" "));
+                }
+            }
+            Format::Json => {
+                output::print_json(&json!({
+                    "articles": output::pages(data, nodes.iter().copied()),
+                    "eccentricities": eccentricities,
+                    "distances": rows,
+                }))?;
+            }
+            Format::Csv => {
+                let titles = nodes
+                    .iter()
+                    .map(|n| data.pages.title(*n))
+                    .collect::<Vec<_>>();
+                let mut header = vec!["title", "eccentricity"];
+                header.extend(&titles);
+                let mut csv = Csv::new(&header)?;
+                for (i, row) in rows.iter().enumerate() {
+                    let ecc = fmt_distance(eccentricities[i]);
+                    let cells = row.iter().map(|d| fmt_distance(*d)).collect::<Vec<_>>();
+                    let mut fields = vec![&titles[i] as &dyn Display, &ecc];
+                    fields.extend(cells.iter().map(|c| c as &dyn Display));
+                    csv.row(&fields)?;
+                }
+                csv.finish()?;
+            }
+        }
+
+        Ok(())
+    }
+}
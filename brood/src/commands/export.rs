@@ -1,15 +1,54 @@
-use std::{io, path::PathBuf};
+use std::path::PathBuf;
 
-use crate::data::Data;
+use crate::{algo, data::Data, error::Result, index::TitleIndex, util::TitleNormalizer};
 
+/// Write the data to a new datafile using the latest format version.
+///
+/// By default, the edges are written in the same encoding they were read in. A
+/// title index is built if the data doesn't have one.
 #[derive(Debug, clap::Parser)]
 pub struct Cmd {
     out: PathBuf,
+
+    /// Sort the edges of every page and store them as compressed adjacency
+    /// lists.
+    #[arg(long, conflicts_with = "unpack")]
+    pack: bool,
+
+    /// Store the edges uncompressed.
+    #[arg(long)]
+    unpack: bool,
+
+    /// Also store the in-edges of every page.
+    #[arg(long)]
+    reverse: bool,
 }
 
 impl Cmd {
-    pub fn run(self, data: Data) -> io::Result<()> {
-        println!(">> Export");
+    pub fn run(self, mut data: Data) -> Result<()> {
+        if self.pack {
+            eprintln!(">> Pack");
+            eprintln!("> Sorting edges");
+            algo::sort_edges(&mut data);
+            eprintln!("> Packing edges");
+            data.graph.pack();
+        } else if self.unpack {
+            eprintln!(">> Unpack");
+            data.graph.unpack();
+        }
+
+        if self.reverse && data.graph.reverse.is_none() {
+            eprintln!(">> Collect in-edges");
+            data.graph.build_reverse();
+        }
+
+        if data.title_index.is_none() {
+            eprintln!(">> Build title index");
+            let normalizer = TitleNormalizer::new();
+            data.title_index = Some(TitleIndex::build(&normalizer, &data.pages));
+        }
+
+        eprintln!(">> Export");
         data.write_to_file(&self.out)?;
 
         Ok(())
@@ -0,0 +1,140 @@
+use std::{fs::File, io::BufReader, path::Path};
+
+use serde_json::json;
+use thousands::Separable;
+
+use crate::{
+    data::{self, Header},
+    error::Result,
+    output::{self, Csv, Format},
+};
+
+/// Show the format version and sections of the datafile.
+///
+/// Files with an older version can be migrated using the export command. The
+/// CSV output only lists the sections.
+#[derive(Debug, clap::Parser)]
+pub struct Cmd {
+    /// Verify the checksums of all sections.
+    #[arg(long, short)]
+    verify: bool,
+}
+
+impl Cmd {
+    pub fn run(&self, brood_data: &Path, format: Format) -> Result<()> {
+        let header = Header::read_from_file(brood_data)?;
+
+        // The verification result of each section, if requested.
+        let mut file = BufReader::new(File::open(brood_data)?);
+        let mut verified = vec![];
+        for section in &header.sections {
+            verified.push(if self.verify {
+                Some(match section.verify(&mut file) {
+                    Ok(()) => "ok".to_string(),
+                    Err(err) => err.to_string(),
+                })
+            } else {
+                None
+            });
+        }
+
+        match format {
+            Format::Text => print_text(&header, &verified),
+            Format::Json => {
+                let sections = header
+                    .sections
+                    .iter()
+                    .zip(&verified)
+                    .map(|(section, verified)| {
+                        json!({
+                            "name": section.name(),
+                            "offset": section.offset,
+                            "len": section.len,
+                            "crc32": format!("{:08x}", section.checksum),
+                            "verified": verified,
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                output::print_json(&json!({
+                    "version": header.version,
+                    "current": header.is_current(),
+                    "latest": data::VERSION,
+                    "pages": header.pages,
+                    "links": header.links,
+                    "sections": sections,
+                }))?;
+            }
+            Format::Csv => {
+                let mut csv = Csv::new(&["name", "offset", "len", "crc32", "verified"])?;
+                for (section, verified) in header.sections.iter().zip(&verified) {
+                    csv.row(&[
+                        &section.name(),
+                        &section.offset,
+                        &section.len,
+                        &format_args!("{:08x}", section.checksum),
+                        &verified.as_deref().unwrap_or(""),
+                    ])?;
+                }
+                csv.finish()?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn print_text(header: &Header, verified: &[Option<String>]) {
+    const W_LABEL: usize = 8;
+    const W_NUM: usize = 13;
+
+    let status = if header.is_current() {
+        "current"
+    } else {
+        "outdated"
+    };
+
+    println!(
+        "{:>W_LABEL$}: {} ({status}, latest is {})",
+        "Version",
+        header.version,
+        data::VERSION
+    );
+
+    println!(
+        "{:>W_LABEL$}: {:>W_NUM$}",
+        "Pages",
+        header.pages.separate_with_underscores()
+    );
+
+    println!(
+        "{:>W_LABEL$}: {:>W_NUM$}",
+        "Links",
+        header.links.separate_with_underscores()
+    );
+
+    if header.sections.is_empty() {
+        println!();
+        println!("This datafile has no section table.");
+    } else {
+        println!();
+        println!("Sections:");
+        for (section, verified) in header.sections.iter().zip(verified) {
+            let mut line = format!(
+                "  {:<8} at {:>W_NUM$}, {:>W_NUM$} bytes, crc32 {:08x}",
+                section.name(),
+                section.offset.separate_with_underscores(),
+                section.len.separate_with_underscores(),
+                section.checksum,
+            );
+            if let Some(verified) = verified {
+                line.push_str(&format!(", {verified}"));
+            }
+            println!("{line}");
+        }
+    }
+
+    if !header.is_current() {
+        println!();
+        println!("Migrate to the latest version using the export command.");
+    }
+}
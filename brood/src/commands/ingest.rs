@@ -11,7 +11,9 @@ use thousands::Separable;
 
 use crate::{
     data::{Data, Link, Page},
+    error::{Error, Result},
     graph::NodeIdx,
+    index::TitleIndex,
     util::TitleNormalizer,
 };
 
@@ -38,14 +40,21 @@ struct JsonPage {
     redirect: Option<String>,
 }
 
-fn read_titles(f: &mut BufReader<File>) -> io::Result<Vec<String>> {
+fn parse_page(line: io::Result<String>, i: usize) -> Result<JsonPage> {
+    serde_json::from_str::<JsonPage>(&line?).map_err(|err| Error::Sift {
+        line: i + 1,
+        msg: err.to_string(),
+    })
+}
+
+fn read_titles(f: &mut BufReader<File>) -> Result<Vec<String>> {
     let size = seek_to_start(f)?;
     let bar = ProgressBar::new(size).with_style(file_progress_style());
 
     let mut titles = vec![];
 
-    for line in bar.wrap_read(f).lines() {
-        let page = serde_json::from_str::<JsonPage>(&line?).unwrap();
+    for (i, line) in bar.wrap_read(f).lines().enumerate() {
+        let page = parse_page(line, i)?;
         titles.push(page.title);
     }
 
@@ -103,14 +112,14 @@ fn read_page_data(
     normalizer: &TitleNormalizer,
     title_lookup: &HashMap<String, (u32, u32)>,
     f: &mut BufReader<File>,
-) -> io::Result<Data> {
+) -> Result<Data> {
     let size = seek_to_start(f)?;
     let bar = ProgressBar::new(size).with_style(file_progress_style());
 
     let mut data = Data::new();
 
     for (i, line) in bar.wrap_read(f).lines().enumerate() {
-        let page = serde_json::from_str::<JsonPage>(&line?).unwrap();
+        let page = parse_page(line, i)?;
         let normalized = normalizer.normalize(&page.title);
 
         let (sift_i, _) = title_lookup[&normalized];
@@ -127,7 +136,7 @@ fn read_page_data(
         data.graph.add_node();
         data.pages.push(Page {
             id: page.id,
-            title: page.title,
+            title: &page.title,
             length: page.length,
             redirect: page.redirect.is_some(),
         });
@@ -158,37 +167,40 @@ pub struct Cmd {
 }
 
 impl Cmd {
-    pub fn run(&self, brood_data: &Path) -> io::Result<()> {
+    pub fn run(&self, brood_data: &Path) -> Result<()> {
         let normalizer = TitleNormalizer::new();
 
-        println!(">> First pass");
+        eprintln!(">> First pass");
         let mut sift_data = BufReader::new(File::open(&self.data)?);
 
-        println!("> Reading titles");
+        eprintln!("> Reading titles");
         let titles = read_titles(&mut sift_data)?;
 
-        println!("> Computing title index lookup table");
+        eprintln!("> Computing title index lookup table");
         let title_lookup = compute_title_lookup(&normalizer, &titles);
         drop(titles); // Don't hoard memory
 
-        println!(">> Second pass");
+        eprintln!(">> Second pass");
 
-        println!("> Reading page data");
-        let data = read_page_data(&normalizer, &title_lookup, &mut sift_data)?;
+        eprintln!("> Reading page data");
+        let mut data = read_page_data(&normalizer, &title_lookup, &mut sift_data)?;
         assert_eq!(data.pages.len(), title_lookup.len());
         drop(title_lookup); // Don't hoard memory
         drop(sift_data); // No longer needed
 
-        println!("> Checking consistency");
-        data.check_consistency();
+        eprintln!("> Checking consistency");
+        data.check_consistency()?;
+
+        eprintln!("> Building title index");
+        data.title_index = Some(TitleIndex::build(&normalizer, &data.pages));
 
-        println!(">> Export");
-        println!(
-            "Pages: {:>13}",
+        eprintln!(">> Export");
+        eprintln!(
+            "> Pages: {:>13}",
             data.pages.len().separate_with_underscores()
         );
-        println!(
-            "Links: {:>13}",
+        eprintln!(
+            "> Links: {:>13}",
             data.links.len().separate_with_underscores()
         );
         data.write_to_file(brood_data)?;
@@ -0,0 +1,59 @@
+use std::path::PathBuf;
+
+use crate::{
+    data::Data,
+    error::Result,
+    landmarks::Landmarks,
+    output::{self, Csv, Format},
+    util,
+};
+
+/// Pick landmark articles and save the distances from and to every article.
+///
+/// With them, `path --algo alt` finds paths while visiting far fewer articles,
+/// and `path-batch --estimate` bounds distances without searching at all. The
+/// file only works with the datafile and global filters it was built with.
+#[derive(Debug, clap::Parser)]
+pub struct Cmd {
+    out: PathBuf,
+
+    /// Number of landmarks. More landmarks give better estimates, but take
+    /// more space and time.
+    #[arg(long, short = 'n', default_value_t = 16)]
+    count: usize,
+
+    #[command(flatten)]
+    jobs: util::Jobs,
+}
+
+impl Cmd {
+    pub fn run(self, data: &Data, format: Format) -> Result<()> {
+        eprintln!(">> Find distances");
+        let landmarks = Landmarks::build(data, self.count, self.jobs.get());
+
+        eprintln!(">> Export");
+        landmarks.write_to_file(&self.out)?;
+
+        match format {
+            Format::Text => {
+                println!("Landmarks:");
+                for node in &landmarks.nodes {
+                    println!("{}", util::fmt_page(&data.pages.get(*node)));
+                }
+            }
+            Format::Json => {
+                output::print_json(&output::pages(data, landmarks.nodes.iter().copied()))?
+            }
+            Format::Csv => {
+                let mut csv = Csv::new(&["title", "redirect"])?;
+                for node in &landmarks.nodes {
+                    let page = data.pages.get(*node);
+                    csv.row(&[&page.title, &page.redirect])?;
+                }
+                csv.finish()?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -1,9 +1,11 @@
-use std::io;
+use serde_json::{json, Value};
 
 use crate::{
     algo::Dijkstra,
     data::Data,
+    error::Result,
     graph::NodeIdx,
+    output::{self, Csv, Format},
     util::{self, TitleNormalizer},
 };
 
@@ -17,8 +19,8 @@ pub struct Cmd {
 }
 
 fn print_path(data: &Data, start: NodeIdx, goal: NodeIdx, path: Option<(u32, Vec<NodeIdx>)>) {
-    let start = &data.pages[start.usize()].title;
-    let goal = &data.pages[goal.usize()].title;
+    let start = data.pages.title(start);
+    let goal = data.pages.title(goal);
 
     let Some((cost, path)) = path else {
         println!("No path found from {start} to {goal}");
@@ -28,29 +30,28 @@ fn print_path(data: &Data, start: NodeIdx, goal: NodeIdx, path: Option<(u32, Vec
     println!("Path found (cost {cost}, length {}):", path.len());
 
     for page in path {
-        println!("{}", util::fmt_page(&data.pages[page.usize()]));
+        println!("{}", util::fmt_page(&data.pages.get(page)));
     }
 }
 
 impl Cmd {
-    pub fn run(self, data: Data) -> io::Result<()> {
+    pub fn run(self, data: &Data, format: Format) -> Result<()> {
         let normalizer = TitleNormalizer::new();
 
-        println!(">> Resolve article");
-        let start = util::resolve_title(&normalizer, &data, &self.start);
-        println!("Start: {}", data.pages[start.usize()].title);
+        eprintln!(">> Resolve article");
+        let start = util::resolve_title(&normalizer, data, &self.start)?;
 
-        println!(">> Search paths");
-        println!("> Preparing dijkstra");
+        eprintln!(">> Search paths");
+        eprintln!("> Preparing dijkstra");
         let mut dijkstra = Dijkstra::new(&data.graph);
-        println!("> Running dijkstra");
+        eprintln!("> Running dijkstra");
         dijkstra.run(
             start,
             |_| false,
-            |source, _edge, _target| !data.pages[source.usize()].redirect as u32,
+            |source, _edge, _target| !data.pages.redirect(source) as u32,
         );
 
-        println!(">> Find longest paths");
+        eprintln!(">> Find longest paths");
         let mut costs = data
             .graph
             .nodes()
@@ -59,10 +60,39 @@ impl Cmd {
             .collect::<Vec<_>>();
         costs.sort_unstable();
 
-        for (cost, goal) in costs.iter().rev().take(self.top) {
-            let path = dijkstra.path(*goal);
-            println!();
-            print_path(&data, start, *goal, Some((*cost, path)));
+        let longest = costs
+            .iter()
+            .rev()
+            .take(self.top)
+            .map(|(cost, goal)| (*goal, Some((*cost, dijkstra.path(*goal)))));
+
+        match format {
+            Format::Text => {
+                println!("Start: {}", data.pages.title(start));
+                for (goal, path) in longest {
+                    println!();
+                    print_path(data, start, goal, path);
+                }
+            }
+            Format::Json => {
+                let paths = longest
+                    .map(|(goal, path)| {
+                        json!({
+                            "goal": output::page(data, goal),
+                            "path": output::path(data, path),
+                        })
+                    })
+                    .collect::<Value>();
+                let result = json!({ "start": output::page(data, start), "paths": paths });
+                output::print_json(&result)?;
+            }
+            Format::Csv => {
+                let mut csv = Csv::new(output::PATH_HEADER)?;
+                for (goal, path) in longest {
+                    csv.path_rows(data, start, goal, path)?;
+                }
+                csv.finish()?;
+            }
         }
 
         Ok(())
@@ -0,0 +1,145 @@
+use std::path::PathBuf;
+
+use serde_json::{json, Value};
+
+use crate::{
+    algo,
+    data::Data,
+    error::Result,
+    graph::NodeIdx,
+    output::{self, Csv, Format},
+    util::{self, TitleNormalizer},
+};
+
+fn parse_damping(s: &str) -> std::result::Result<f64, String> {
+    let damping = s.parse::<f64>().map_err(|err| err.to_string())?;
+    if (0.0..1.0).contains(&damping) {
+        Ok(damping)
+    } else {
+        Err("must be at least 0 and less than 1".to_string())
+    }
+}
+
+/// Rank the articles by PageRank.
+///
+/// Links removed by the global filters are not followed, and with `-R`,
+/// redirects pass their rank on to their targets. As CSV, one row is printed
+/// per listed article.
+#[derive(Debug, clap::Parser)]
+pub struct Cmd {
+    /// Probability of following a link instead of jumping to a random article.
+    #[arg(long, short, default_value_t = 0.85, value_parser = parse_damping)]
+    damping: f64,
+
+    /// Stop once the ranks change by less than this in total.
+    #[arg(long, short, default_value_t = 1e-6)]
+    tolerance: f64,
+
+    /// Stop after this many iterations, even if the ranks haven't settled.
+    #[arg(long, default_value_t = 100)]
+    max_iterations: usize,
+
+    /// Only jump to the articles listed in this file, one title per line.
+    #[arg(long, value_name = "FILE")]
+    personalize: Option<PathBuf>,
+
+    /// How many of the highest ranked articles to list.
+    #[arg(long, short, default_value_t = 20)]
+    limit: usize,
+
+    /// List every article.
+    #[arg(long, conflicts_with = "limit")]
+    all: bool,
+
+    #[command(flatten)]
+    jobs: util::Jobs,
+}
+
+impl Cmd {
+    fn teleport(&self, data: &Data) -> Result<Vec<f64>> {
+        let len = data.pages.len();
+        let uniform = vec![1.0 / len as f64; len];
+        let Some(file) = &self.personalize else {
+            return Ok(uniform);
+        };
+
+        let nodes = util::resolve_title_file(&TitleNormalizer::new(), data, file)?;
+        if nodes.is_empty() {
+            eprintln!("> No articles listed, jumping to all articles instead");
+            return Ok(uniform);
+        }
+
+        let mut teleport = vec![0.0; len];
+        for node in &nodes {
+            teleport[node.usize()] += 1.0 / nodes.len() as f64;
+        }
+        Ok(teleport)
+    }
+
+    pub fn run(self, data: &Data, format: Format) -> Result<()> {
+        if self.personalize.is_some() {
+            eprintln!(">> Resolve articles");
+        }
+        let teleport = self.teleport(data)?;
+
+        eprintln!(">> Rank articles");
+        let (ranks, iterations, converged) = algo::pagerank(
+            &data.graph,
+            &teleport,
+            self.damping,
+            self.tolerance,
+            self.max_iterations,
+            self.jobs.get(),
+        );
+        if converged {
+            eprintln!("> Converged after {iterations} iterations");
+        } else {
+            eprintln!("> Stopped after {iterations} iterations without converging");
+        }
+
+        let mut ranked = data.graph.nodes().collect::<Vec<_>>();
+        ranked.sort_by(|a, b| ranks[b.usize()].total_cmp(&ranks[a.usize()]).then(a.cmp(b)));
+        if !self.all {
+            ranked.truncate(self.limit);
+        }
+
+        match format {
+            Format::Text => {
+                for (i, node) in ranked.iter().enumerate() {
+                    println!(
+                        "{:4}. {:.3e} {}",
+                        i + 1,
+                        ranks[node.usize()],
+                        util::fmt_page(&data.pages.get(*node))
+                    );
+                }
+            }
+            Format::Json => {
+                let pages = ranked
+                    .iter()
+                    .map(|node| page_json(data, *node, ranks[node.usize()]))
+                    .collect::<Value>();
+                output::print_json(&json!({
+                    "iterations": iterations,
+                    "converged": converged,
+                    "pages": pages,
+                }))?;
+            }
+            Format::Csv => {
+                let mut csv = Csv::new(&["rank", "title", "redirect", "score"])?;
+                for (i, node) in ranked.iter().enumerate() {
+                    let page = data.pages.get(*node);
+                    csv.row(&[&(i + 1), &page.title, &page.redirect, &ranks[node.usize()]])?;
+                }
+                csv.finish()?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn page_json(data: &Data, node: NodeIdx, score: f64) -> Value {
+    let page = data.pages.get(node);
+    json!({ "title": page.title, "redirect": page.redirect, "score": score })
+}
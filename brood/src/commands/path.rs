@@ -1,9 +1,22 @@
-use std::io;
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufRead, BufReader},
+    path::{Path, PathBuf},
+};
+
+use regex::Regex;
+use serde_json::json;
+use thousands::Separable;
 
 use crate::{
-    algo::Dijkstra,
-    data::Data,
-    graph::NodeIdx,
+    algo::{self, AStar, BidiBfs, Dijkstra, Geodesics},
+    commands::stats::degrees,
+    data::{Data, Load},
+    error::{Error, Result},
+    graph::{EdgeIdx, NodeIdx},
+    landmarks::Landmarks,
+    output::{self, Csv, Format},
     util::{self, TitleNormalizer},
 };
 
@@ -16,72 +29,723 @@ pub struct Cmd {
     // Search for a path in both directions.
     #[arg(long, short)]
     bidi: bool,
+
+    /// How to search for the path.
+    #[arg(long, short, value_enum, default_value_t)]
+    algo: Algo,
+
+    /// Landmarks for `--algo alt`, see the landmarks command.
+    #[arg(long, value_name = "FILE", required_if_eq("algo", "alt"))]
+    landmarks: Option<PathBuf>,
+
+    /// Find all shortest paths, count them and rank the articles along them.
+    ///
+    /// As CSV, one row is printed per page of every listed path, together with
+    /// the number of shortest paths passing through it.
+    #[arg(long, conflicts_with = "bidi")]
+    all: bool,
+
+    /// Find the k shortest paths that don't visit any article twice.
+    #[arg(long, conflicts_with_all = ["bidi", "all"])]
+    k: Option<usize>,
+
+    /// How many of all shortest paths and articles along them to list.
+    #[arg(
+        long,
+        short,
+        default_value_t = 10,
+        requires = "all",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..),
+    )]
+    limit: usize,
+
+    /// How much following a link costs.
+    ///
+    /// Following a link away from a redirect is always free.
+    #[arg(long, value_enum, default_value_t)]
+    cost: CostModel,
+
+    /// Costs for `--cost weights`.
+    ///
+    /// Every line contains the title of a link's source, of its target and the
+    /// cost of following it, separated by tabs. Costs range from 1 to
+    /// 4294967294. Other links cost 1.
+    #[arg(long, value_name = "FILE", required_if_eq("cost", "weights"))]
+    weights: Option<PathBuf>,
+
+    #[command(flatten)]
+    rules: Rules,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum CostModel {
+    /// Every link costs 1.
+    #[default]
+    Hops,
+    /// Every link costs 1, plus 1 for every 1000 characters before it in the
+    /// article.
+    Position,
+    /// Every link costs 1, plus 1 for every doubling of its target's inlinks.
+    Hubs,
+    /// Links in structures like tables or infoboxes cost 4, other links 1.
+    Structure,
+    /// Links cost what the `--weights` file says.
+    Weights,
+}
+
+/// See [`CostModel::Structure`].
+const STRUCTURE_COST: u32 = 4;
+
+/// See [`CostModel::Position`].
+const POSITION_STEP: u32 = 1000;
+
+impl CostModel {
+    fn load(self) -> Load {
+        match self {
+            Self::Hops | Self::Hubs | Self::Weights => Load::Graph,
+            Self::Structure => Load::Flags,
+            Self::Position => Load::Full,
+        }
+    }
+}
+
+/// Read the costs of a `--weights` file.
+fn read_weights(
+    normalizer: &TitleNormalizer,
+    data: &Data,
+    file: &Path,
+) -> Result<HashMap<(NodeIdx, NodeIdx), u32>> {
+    parse_weights(normalizer, data, BufReader::new(File::open(file)?))
+}
+
+fn parse_weights(
+    normalizer: &TitleNormalizer,
+    data: &Data,
+    reader: impl BufRead,
+) -> Result<HashMap<(NodeIdx, NodeIdx), u32>> {
+    let mut weights = HashMap::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let invalid = |msg: String| Error::Input { line: i + 1, msg };
+        let [source, target, cost] = line.split('\t').collect::<Vec<_>>()[..] else {
+            return Err(invalid(
+                "expected source, target and cost separated by tabs".to_string(),
+            ));
+        };
+        let find = |title: &str| {
+            util::find_title(normalizer, data, title)
+                .ok_or_else(|| invalid(format!("article not found: {title:?}")))
+        };
+        let cost = cost
+            .trim()
+            .parse::<u32>()
+            .map_err(|err| invalid(err.to_string()))?;
+        // Free links could form loops, and u32::MAX marks blocked links.
+        if cost == 0 || cost == u32::MAX {
+            return Err(invalid(format!(
+                "cost must be between 1 and {}, got {cost}",
+                u32::MAX - 1
+            )));
+        }
+        weights.insert((find(source)?, find(target)?), cost);
+    }
+    Ok(weights)
+}
+
+/// Restrictions on the articles and links a path may use.
+///
+/// They are checked while searching, unlike the global filters which remove
+/// links from the whole graph. The start, goal and via articles are never
+/// avoided.
+#[derive(Debug, clap::Args)]
+struct Rules {
+    /// Never pass through this article.
+    #[arg(long, value_name = "TITLE")]
+    avoid: Vec<String>,
+
+    /// Never pass through pages whose title matches this regex.
+    #[arg(long, value_name = "REGEX")]
+    exclude: Vec<Regex>,
+
+    /// Pass through these articles, in the given order.
+    #[arg(long, value_name = "TITLE", conflicts_with_all = ["all", "k"])]
+    via: Vec<String>,
+
+    /// Never follow links in parentheses.
+    #[arg(long)]
+    no_parens: bool,
+
+    /// Never follow links in structures like tables or infoboxes.
+    #[arg(long)]
+    no_structure: bool,
+
+    /// Never pass through pages with more inlinks than this.
+    #[arg(long, value_name = "N")]
+    max_indegree: Option<usize>,
+}
+
+impl Rules {
+    fn load(&self) -> Load {
+        if self.no_parens || self.no_structure {
+            Load::Flags
+        } else {
+            Load::Graph
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.avoid.is_empty()
+            && self.exclude.is_empty()
+            && self.max_indegree.is_none()
+            && !self.no_parens
+            && !self.no_structure
+    }
+}
+
+/// The [`Rules`] and [`CostModel`] applied to the data.
+struct Costs<'a> {
+    data: &'a Data,
+    model: CostModel,
+    /// Whether a path may not pass through a page, or empty if there are no
+    /// such pages.
+    blocked: Vec<bool>,
+    via: Vec<NodeIdx>,
+    no_parens: bool,
+    no_structure: bool,
+    /// Only for [`CostModel::Hubs`].
+    indegrees: Vec<usize>,
+    /// Only for [`CostModel::Weights`].
+    weights: HashMap<(NodeIdx, NodeIdx), u32>,
+    /// Only for [`Algo::Alt`].
+    landmarks: Option<Landmarks>,
+}
+
+impl<'a> Costs<'a> {
+    fn new(
+        normalizer: &TitleNormalizer,
+        data: &'a Data,
+        cmd: &Cmd,
+        endpoints: [NodeIdx; 2],
+    ) -> Result<Self> {
+        let rules = &cmd.rules;
+        let via = rules
+            .via
+            .iter()
+            .map(|title| util::resolve_title(normalizer, data, title))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut blocked = vec![];
+        if !rules.is_empty() {
+            blocked = vec![false; data.pages.len()];
+
+            for title in &rules.avoid {
+                blocked[util::resolve_title(normalizer, data, title)?.usize()] = true;
+            }
+
+            if !rules.exclude.is_empty() {
+                for (i, page) in data.pages.iter().enumerate() {
+                    if rules.exclude.iter().any(|r| r.is_match(page.title)) {
+                        blocked[i] = true;
+                    }
+                }
+            }
+
+            if let Some(max) = rules.max_indegree {
+                for (i, indegree) in degrees::indegrees(data).into_iter().enumerate() {
+                    if indegree > max {
+                        blocked[i] = true;
+                    }
+                }
+            }
+
+            for node in endpoints.iter().chain(&via) {
+                blocked[node.usize()] = false;
+            }
+        }
+
+        let indegrees = match cmd.cost {
+            CostModel::Hubs => degrees::indegrees(data),
+            _ => vec![],
+        };
+
+        let weights = match &cmd.weights {
+            Some(file) if cmd.cost == CostModel::Weights => read_weights(normalizer, data, file)?,
+            _ => HashMap::new(),
+        };
+
+        let landmarks = match &cmd.landmarks {
+            Some(file) if cmd.algo == Algo::Alt => {
+                eprintln!("> Reading landmarks");
+                Some(Landmarks::read_from_file(file, data)?)
+            }
+            _ => None,
+        };
+
+        Ok(Self {
+            data,
+            model: cmd.cost,
+            blocked,
+            via,
+            no_parens: rules.no_parens,
+            no_structure: rules.no_structure,
+            indegrees,
+            weights,
+            landmarks,
+        })
+    }
+
+    /// The cost of an edge, or `u32::MAX` if it may not be used.
+    fn cost(&self, source: NodeIdx, edge: EdgeIdx, target: NodeIdx) -> u32 {
+        if self.blocked.get(target.usize()).copied().unwrap_or(false) {
+            return u32::MAX;
+        }
+
+        if self.no_parens || self.no_structure {
+            let link = self.data.links.get(edge);
+            if (self.no_parens && link.in_parens()) || (self.no_structure && link.in_structure()) {
+                return u32::MAX;
+            }
+        }
+
+        if self.data.pages.redirect(source) {
+            return 0;
+        }
+
+        match self.model {
+            CostModel::Hops => 1,
+            CostModel::Position => 1 + self.data.links.get(edge).start / POSITION_STEP,
+            CostModel::Hubs => 1 + (self.indegrees[target.usize()] + 1).ilog2(),
+            CostModel::Structure if self.data.links.get(edge).in_structure() => STRUCTURE_COST,
+            CostModel::Structure => 1,
+            CostModel::Weights => self.weights.get(&(source, target)).copied().unwrap_or(1),
+        }
+    }
+
+    /// Whether every edge costs 0 or 1.
+    fn unweighted(&self) -> bool {
+        self.model == CostModel::Hops
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Algo {
+    /// Dijkstra's algorithm, starting from the start only.
+    #[default]
+    Dijkstra,
+    /// Breadth-first search from both ends at once. Much faster on large
+    /// graphs, but requires the in-edges, which are built if necessary.
+    Bfs,
+    /// A* search, estimating the remaining distance with landmarks. Visits far
+    /// fewer articles than dijkstra, but requires `--landmarks`.
+    Alt,
 }
 
-fn search_path(data: &Data, start: NodeIdx, goal: NodeIdx) -> Option<(u32, Vec<NodeIdx>)> {
-    println!("> Preparing dijkstra");
-    let mut dijkstra = Dijkstra::new(&data.graph);
-    println!("> Running dijkstra");
+/// Find the shortest path from start to goal without printing any progress.
+pub fn shortest_path(data: &Data, start: NodeIdx, goal: NodeIdx) -> Option<(u32, Vec<NodeIdx>)> {
+    shortest_path_with(&mut Dijkstra::new(&data.graph), data, start, goal)
+}
+
+/// Like [`shortest_path`], but reuses the buffers of an existing search.
+pub fn shortest_path_with(
+    dijkstra: &mut Dijkstra<'_>,
+    data: &Data,
+    start: NodeIdx,
+    goal: NodeIdx,
+) -> Option<(u32, Vec<NodeIdx>)> {
     dijkstra.run(
         start,
         |node| node == goal,
-        |source, _edge, _target| !data.pages[source.usize()].redirect as u32,
+        |source, _edge, _target| !data.pages.redirect(source) as u32,
     );
 
     if dijkstra.cost(goal) == u32::MAX {
         return None;
     }
 
-    println!("> Collecting path");
     let cost = dijkstra.cost(goal);
     let path = dijkstra.path(goal);
     Some((cost, path))
 }
 
-fn print_path(data: &Data, start: NodeIdx, goal: NodeIdx, path: Option<(u32, Vec<NodeIdx>)>) {
-    let start = &data.pages[start.usize()].title;
-    let goal = &data.pages[goal.usize()].title;
+pub fn search_path(data: &Data, start: NodeIdx, goal: NodeIdx) -> Option<(u32, Vec<NodeIdx>)> {
+    eprintln!("> Running dijkstra");
+    shortest_path(data, start, goal)
+}
+
+pub fn print_path(data: &Data, start: NodeIdx, goal: NodeIdx, path: Option<(u32, Vec<NodeIdx>)>) {
+    let start = data.pages.title(start);
+    let goal = data.pages.title(goal);
 
     let Some((cost, path)) = path else {
         println!("No path found from {start} to {goal}");
         return;
     };
 
-    println!("Path found (cost {cost}, length {}):", path.len());
+    println!(
+        "Path found (cost {cost}, {} hops, length {}):",
+        path.len() - 1,
+        path.len()
+    );
 
     for page in path {
-        println!("{}", util::fmt_page(&data.pages[page.usize()]));
+        println!("{}", util::fmt_page(&data.pages.get(page)));
+    }
+}
+
+fn print_geodesics(
+    data: &Data,
+    start: NodeIdx,
+    goal: NodeIdx,
+    geodesics: &Geodesics,
+    limit: usize,
+) {
+    let count = geodesics.count();
+    let paths = geodesics.paths(limit);
+    println!(
+        "{} shortest paths found (cost {}, length {}), showing {}:",
+        count.separate_with_underscores(),
+        geodesics.cost(),
+        paths.first().map_or(0, Vec::len),
+        paths.len(),
+    );
+    for path in paths {
+        println!();
+        for page in path {
+            println!("{}", util::fmt_page(&data.pages.get(page)));
+        }
+    }
+
+    println!();
+    println!(
+        "Articles on most paths from {} to {}:",
+        data.pages.title(start),
+        data.pages.title(goal)
+    );
+    for (i, (node, through)) in geodesics
+        .intermediates()
+        .into_iter()
+        .take(limit)
+        .enumerate()
+    {
+        println!(
+            "{:3}. {} ({} of {} paths)",
+            i + 1,
+            util::fmt_page(&data.pages.get(node)),
+            through.separate_with_underscores(),
+            count.separate_with_underscores()
+        );
     }
 }
 
 impl Cmd {
-    pub fn run(self, data: Data) -> io::Result<()> {
+    pub fn load(&self) -> Load {
+        self.rules.load().max(self.cost.load())
+    }
+
+    pub fn in_edges(&self) -> bool {
+        self.algo == Algo::Bfs
+    }
+
+    fn search_segment(
+        &self,
+        costs: &Costs<'_>,
+        start: NodeIdx,
+        goal: NodeIdx,
+    ) -> Option<(u32, Vec<NodeIdx>)> {
+        let graph = &costs.data.graph;
+        let cost = |source, edge, target| costs.cost(source, edge, target);
+
+        // The in-edges are collected before running the command, see
+        // `Self::in_edges`. This only guards against callers that don't.
+        if self.algo == Algo::Bfs && graph.reverse.is_none() {
+            eprintln!("> No in-edges, falling back to dijkstra");
+        } else if self.algo == Algo::Bfs && !costs.unweighted() {
+            eprintln!("> Links have weighted costs, falling back to dijkstra");
+        } else if self.algo == Algo::Bfs {
+            eprintln!("> Running bidirectional bfs");
+            return BidiBfs::new(graph).run(start, goal, cost);
+        } else if let Some(landmarks) = &costs.landmarks {
+            eprintln!("> Running alt");
+            let mut astar = AStar::new(graph);
+            let path = astar.run(start, goal, |node| landmarks.lower_bound(node, goal), cost);
+            eprintln!(
+                "> Visited {} articles",
+                astar.reached().separate_with_underscores()
+            );
+            return path;
+        }
+
+        eprintln!("> Running dijkstra");
+        let mut dijkstra = Dijkstra::new(graph);
+        dijkstra.run(start, |node| node == goal, cost);
+        if dijkstra.cost(goal) == u32::MAX {
+            return None;
+        }
+        Some((dijkstra.cost(goal), dijkstra.path(goal)))
+    }
+
+    /// Passes through the via articles in order, or in reverse order when
+    /// searching backward.
+    fn search(
+        &self,
+        costs: &Costs<'_>,
+        start: NodeIdx,
+        goal: NodeIdx,
+        backward: bool,
+    ) -> Option<(u32, Vec<NodeIdx>)> {
+        let mut waypoints = vec![start];
+        if backward {
+            waypoints.extend(costs.via.iter().rev());
+        } else {
+            waypoints.extend(&costs.via);
+        }
+        waypoints.push(goal);
+
+        let mut cost = 0;
+        let mut path = vec![start];
+        for pair in waypoints.windows(2) {
+            let (segment_cost, segment) = self.search_segment(costs, pair[0], pair[1])?;
+            cost += segment_cost;
+            path.extend(&segment[1..]);
+        }
+        Some((cost, path))
+    }
+
+    fn run_all(
+        &self,
+        costs: &Costs<'_>,
+        format: Format,
+        start: NodeIdx,
+        goal: NodeIdx,
+    ) -> Result<()> {
+        let data = costs.data;
+
+        eprintln!(">> Find all shortest paths");
+        let geodesics = Geodesics::find(&data.graph, start, goal, |source, edge, target| {
+            costs.cost(source, edge, target)
+        })
+        .map_err(|loop_| {
+            Error::Search(format!(
+                "shortest paths pass through a loop of free links at {}",
+                data.pages.title(loop_.0)
+            ))
+        })?;
+
+        match format {
+            Format::Text => {
+                println!("Start: {}", data.pages.title(start));
+                println!("Goal:  {}", data.pages.title(goal));
+                println!();
+                match &geodesics {
+                    Some(geodesics) => print_geodesics(data, start, goal, geodesics, self.limit),
+                    None => print_path(data, start, goal, None),
+                }
+            }
+            Format::Json => {
+                let mut result = json!({
+                    "start": output::page(data, start),
+                    "goal": output::page(data, goal),
+                    "cost": null,
+                    "count": 0,
+                    "paths": [],
+                    "intermediates": [],
+                });
+                if let Some(geodesics) = &geodesics {
+                    result["cost"] = geodesics.cost().into();
+                    result["count"] = geodesics.count().into();
+                    result["paths"] = geodesics
+                        .paths(self.limit)
+                        .into_iter()
+                        .map(|path| output::pages(data, path))
+                        .collect();
+                    result["intermediates"] = geodesics
+                        .intermediates()
+                        .into_iter()
+                        .take(self.limit)
+                        .map(|(node, through)| {
+                            let mut page = output::page(data, node);
+                            page["paths"] = through.into();
+                            page
+                        })
+                        .collect();
+                }
+                output::print_json(&result)?;
+            }
+            Format::Csv => {
+                let mut csv = Csv::new(&["path", "cost", "step", "title", "redirect", "through"])?;
+                if let Some(geodesics) = &geodesics {
+                    let cost = geodesics.cost();
+                    for (i, path) in geodesics.paths(self.limit).into_iter().enumerate() {
+                        for (step, node) in path.into_iter().enumerate() {
+                            let page = data.pages.get(node);
+                            let through = geodesics.through(node);
+                            csv.row(&[&i, &cost, &step, &page.title, &page.redirect, &through])?;
+                        }
+                    }
+                }
+                csv.finish()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn run_k(
+        &self,
+        costs: &Costs<'_>,
+        format: Format,
+        start: NodeIdx,
+        goal: NodeIdx,
+        k: usize,
+    ) -> Result<()> {
+        let data = costs.data;
+
+        eprintln!(">> Find {k} shortest paths");
+        let paths = algo::k_shortest_paths(&data.graph, start, goal, k, |source, edge, target| {
+            costs.cost(source, edge, target)
+        });
+
+        match format {
+            Format::Text => {
+                println!("Start: {}", data.pages.title(start));
+                println!("Goal:  {}", data.pages.title(goal));
+                if paths.is_empty() {
+                    println!();
+                    print_path(data, start, goal, None);
+                }
+                for path in paths {
+                    println!();
+                    print_path(data, start, goal, Some(path));
+                }
+            }
+            Format::Json => {
+                let result = json!({
+                    "start": output::page(data, start),
+                    "goal": output::page(data, goal),
+                    "paths": paths
+                        .into_iter()
+                        .map(|path| output::path(data, Some(path)))
+                        .collect::<Vec<_>>(),
+                });
+                output::print_json(&result)?;
+            }
+            Format::Csv => {
+                let mut csv = Csv::new(&["path", "cost", "step", "title", "redirect"])?;
+                for (i, (cost, path)) in paths.into_iter().enumerate() {
+                    for (step, node) in path.into_iter().enumerate() {
+                        let page = data.pages.get(node);
+                        csv.row(&[&i, &cost, &step, &page.title, &page.redirect])?;
+                    }
+                }
+                csv.finish()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn run(self, data: &Data, format: Format) -> Result<()> {
         let normalizer = TitleNormalizer::new();
 
-        println!(">> Resolve articles");
-        let start = util::resolve_title(&normalizer, &data, &self.start);
-        let goal = util::resolve_title(&normalizer, &data, &self.goal);
-        println!("Start: {}", data.pages[start.usize()].title);
-        println!("Goal:  {}", data.pages[goal.usize()].title);
+        eprintln!(">> Resolve articles");
+        let start = util::resolve_title(&normalizer, data, &self.start)?;
+        let goal = util::resolve_title(&normalizer, data, &self.goal)?;
+        let costs = Costs::new(&normalizer, data, &self, [start, goal])?;
+
+        if self.all {
+            return self.run_all(&costs, format, start, goal);
+        }
+
+        if let Some(k) = self.k {
+            return self.run_k(&costs, format, start, goal, k);
+        }
 
         if self.bidi {
-            println!(">> Find path forward");
-            let forward = search_path(&data, start, goal);
-            println!(">> Find path backward");
-            let backward = search_path(&data, goal, start);
-
-            println!();
-            print_path(&data, start, goal, forward);
-            println!();
-            print_path(&data, goal, start, backward);
+            eprintln!(">> Find path forward");
+        } else {
+            eprintln!(">> Find path");
+        }
+        let forward = self.search(&costs, start, goal, false);
+        let backward = if self.bidi {
+            eprintln!(">> Find path backward");
+            Some(self.search(&costs, goal, start, true))
         } else {
-            println!(">> Find path");
-            let path = search_path(&data, start, goal);
+            None
+        };
 
-            println!();
-            print_path(&data, start, goal, path);
+        match format {
+            Format::Text => {
+                println!("Start: {}", data.pages.title(start));
+                println!("Goal:  {}", data.pages.title(goal));
+                println!();
+                print_path(data, start, goal, forward);
+                if let Some(backward) = backward {
+                    println!();
+                    print_path(data, goal, start, backward);
+                }
+            }
+            Format::Json => {
+                let mut result = json!({
+                    "start": output::page(data, start),
+                    "goal": output::page(data, goal),
+                    "forward": output::path(data, forward),
+                });
+                if let Some(backward) = backward {
+                    result["backward"] = output::path(data, backward);
+                }
+                output::print_json(&result)?;
+            }
+            Format::Csv => {
+                let mut csv = Csv::new(output::PATH_HEADER)?;
+                csv.path_rows(data, start, goal, forward)?;
+                if let Some(backward) = backward {
+                    csv.path_rows(data, goal, start, backward)?;
+                }
+                csv.finish()?;
+            }
         }
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+    use crate::data::Page;
+
+    #[test]
+    fn limit_at_least_one() {
+        let parse = |limit| Cmd::try_parse_from(["path", "A", "B", "--all", "--limit", limit]);
+        assert_eq!(parse("1").unwrap().limit, 1);
+        assert!(parse("0").is_err());
+    }
+
+    #[test]
+    fn weights_in_range() {
+        let normalizer = TitleNormalizer::new();
+        let mut data = Data::new();
+        for (i, title) in ["A", "B"].into_iter().enumerate() {
+            data.pages.push(Page {
+                id: i as u32,
+                title,
+                length: 0,
+                redirect: false,
+            });
+        }
+        let parse = |text: &str| parse_weights(&normalizer, &data, text.as_bytes());
+
+        let weights = parse("A\tB\t3\n\nB\tA\t4294967294\n").unwrap();
+        assert_eq!(weights[&(NodeIdx(0), NodeIdx(1))], 3);
+        assert_eq!(weights[&(NodeIdx(1), NodeIdx(0))], u32::MAX - 1);
+        for text in ["A\tB\t1\nB\tA\t0\n", "A\tB\t1\nB\tA\t4294967295\n"] {
+            assert!(matches!(parse(text), Err(Error::Input { line: 2, .. })));
+        }
+    }
+}
@@ -0,0 +1,303 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use indicatif::ProgressBar;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::{
+    algo::Dijkstra,
+    commands::path,
+    data::Data,
+    error::{Error, Result},
+    graph::NodeIdx,
+    landmarks::Landmarks,
+    output::{self, Format},
+    util::{self, TitleNormalizer},
+};
+
+#[derive(Deserialize)]
+struct JsonPair {
+    start: String,
+    goal: String,
+}
+
+struct Pair {
+    start: String,
+    goal: String,
+}
+
+fn parse_pair(line: &str, i: usize) -> Result<Pair> {
+    let invalid = |msg: String| Error::Input { line: i + 1, msg };
+
+    if line.starts_with('{') {
+        let pair =
+            serde_json::from_str::<JsonPair>(line).map_err(|err| invalid(err.to_string()))?;
+        return Ok(Pair {
+            start: pair.start,
+            goal: pair.goal,
+        });
+    }
+
+    match line.split('\t').collect::<Vec<_>>()[..] {
+        [start, goal] => Ok(Pair {
+            start: start.to_string(),
+            goal: goal.to_string(),
+        }),
+        _ => Err(invalid(
+            "expected start and goal separated by a tab".to_string(),
+        )),
+    }
+}
+
+fn read_pairs(file: &Path) -> Result<Vec<Pair>> {
+    let reader = BufReader::new(File::open(file)?);
+    let mut pairs = vec![];
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        pairs.push(parse_pair(&line, i)?);
+    }
+    Ok(pairs)
+}
+
+/// Resolve every distinct title once.
+///
+/// Titles without a page map to `None`. Unlike the path command, the user is
+/// never asked to pick a suggestion.
+fn resolve_titles(
+    normalizer: &TitleNormalizer,
+    data: &Data,
+    pairs: &[Pair],
+) -> HashMap<String, Option<NodeIdx>> {
+    let mut nodes = HashMap::new();
+    for pair in pairs {
+        for title in [&pair.start, &pair.goal] {
+            if !nodes.contains_key(title) {
+                let node = util::find_title(normalizer, data, title)
+                    .map(|node| util::resolve_redirects(data, node));
+                nodes.insert(title.clone(), node);
+            }
+        }
+    }
+    nodes
+}
+
+enum Outcome {
+    Found(u32, Vec<NodeIdx>),
+    Estimated { lower: u32, upper: Option<u32> },
+    Unreachable,
+    NotFound(String),
+}
+
+impl Outcome {
+    fn status(&self) -> &'static str {
+        match self {
+            Self::Found(_, _) => "found",
+            Self::Estimated { .. } => "estimated",
+            Self::Unreachable => "unreachable",
+            Self::NotFound(_) => "not-found",
+        }
+    }
+}
+
+/// Search for a path, or only estimate its cost if there are landmarks.
+fn find(
+    dijkstra: &mut Dijkstra<'_>,
+    data: &Data,
+    landmarks: Option<&Landmarks>,
+    start: NodeIdx,
+    goal: NodeIdx,
+) -> Outcome {
+    if let Some(landmarks) = landmarks {
+        return Outcome::Estimated {
+            lower: landmarks.lower_bound(start, goal),
+            upper: landmarks.upper_bound(start, goal),
+        };
+    }
+
+    match path::shortest_path_with(dijkstra, data, start, goal) {
+        Some((cost, path)) => Outcome::Found(cost, path),
+        None => Outcome::Unreachable,
+    }
+}
+
+fn print_outcome(
+    out: &mut impl Write,
+    data: &Data,
+    format: Format,
+    pair: &Pair,
+    outcome: Outcome,
+) -> io::Result<()> {
+    let (start, goal) = (&pair.start, &pair.goal);
+    match format {
+        Format::Text => match outcome {
+            Outcome::Found(cost, path) => {
+                let titles = path
+                    .iter()
+                    .map(|n| data.pages.title(*n))
+                    .collect::<Vec<_>>();
+                writeln!(
+                    out,
+                    "{start} -> {goal}: cost {cost}, {} hops: {}",
+                    path.len() - 1,
+                    titles.join(" > ")
+                )
+            }
+            Outcome::Estimated {
+                lower,
+                upper: Some(upper),
+            } => writeln!(out, "{start} -> {goal}: cost between {lower} and {upper}"),
+            Outcome::Estimated { lower, upper: None } => {
+                writeln!(out, "{start} -> {goal}: cost at least {lower}")
+            }
+            Outcome::Unreachable => writeln!(out, "{start} -> {goal}: unreachable"),
+            Outcome::NotFound(title) => {
+                writeln!(out, "{start} -> {goal}: article not found: {title:?}")
+            }
+        },
+        Format::Json => {
+            let mut record = json!({ "start": start, "goal": goal, "status": outcome.status() });
+            match outcome {
+                Outcome::Found(cost, path) => {
+                    record["cost"] = cost.into();
+                    record["hops"] = (path.len() - 1).into();
+                    record["path"] = output::pages(data, path);
+                }
+                Outcome::Estimated { lower, upper } => {
+                    record["lower"] = lower.into();
+                    record["upper"] = upper.into();
+                }
+                Outcome::Unreachable => {}
+                Outcome::NotFound(title) => record["missing"] = Value::String(title),
+            }
+            serde_json::to_writer(&mut *out, &record)?;
+            writeln!(out)
+        }
+        Format::Csv => {
+            let status = outcome.status();
+            if let Outcome::Estimated { lower, upper } = outcome {
+                let upper = upper.map(|u| u.to_string()).unwrap_or_default();
+                return output::write_csv_row(out, &[start, goal, &status, &lower, &upper]);
+            }
+            let (cost, hops, path) = match outcome {
+                Outcome::Found(cost, path) => {
+                    let titles = path
+                        .iter()
+                        .map(|n| data.pages.title(*n))
+                        .collect::<Vec<_>>();
+                    (
+                        cost.to_string(),
+                        (path.len() - 1).to_string(),
+                        titles.join("|"),
+                    )
+                }
+                _ => (String::new(), String::new(), String::new()),
+            };
+            output::write_csv_row(out, &[start, goal, &status, &cost, &hops, &path])
+        }
+    }
+}
+
+/// Find the shortest paths between many pairs of articles.
+///
+/// Every line of the file contains a start and goal title, either separated by
+/// a tab or as a JSON object like `{"start": "...", "goal": "..."}`. Empty
+/// lines are skipped.
+///
+/// One record is printed per pair, in the order of the file, as soon as its
+/// search has finished. As JSON, every record is a separate line. As CSV, the
+/// titles of a path are separated by `|`.
+///
+/// With `--estimate`, no paths are searched. Instead, landmarks bound the cost
+/// of each path from below and, if possible, from above. As CSV, the cost, hops
+/// and path columns are replaced by lower and upper columns.
+#[derive(Debug, clap::Parser)]
+pub struct Cmd {
+    pairs: PathBuf,
+
+    /// Estimate the costs with these landmarks, see the landmarks command.
+    #[arg(long, value_name = "LANDMARKS")]
+    estimate: Option<PathBuf>,
+
+    #[command(flatten)]
+    jobs: util::Jobs,
+}
+
+impl Cmd {
+    pub fn run(self, data: &Data, format: Format) -> Result<()> {
+        let normalizer = TitleNormalizer::new();
+
+        eprintln!(">> Read pairs");
+        let pairs = read_pairs(&self.pairs)?;
+
+        eprintln!(">> Resolve articles");
+        let nodes = resolve_titles(&normalizer, data, &pairs);
+
+        let landmarks = match &self.estimate {
+            Some(file) => {
+                eprintln!(">> Read landmarks");
+                Some(Landmarks::read_from_file(file, data)?)
+            }
+            None => None,
+        };
+
+        if landmarks.is_some() {
+            eprintln!(">> Estimate paths");
+        } else {
+            eprintln!(">> Find paths");
+        }
+        let mut out = BufWriter::new(io::stdout().lock());
+        if format == Format::Csv && landmarks.is_some() {
+            output::write_csv_row(
+                &mut out,
+                &[&"start", &"goal", &"status", &"lower", &"upper"],
+            )?;
+        } else if format == Format::Csv {
+            output::write_csv_row(
+                &mut out,
+                &[&"start", &"goal", &"status", &"cost", &"hops", &"path"],
+            )?;
+        }
+
+        let bar = ProgressBar::new(pairs.len() as u64);
+
+        // Searches finish out of order, but records are printed in order.
+        let mut pending = BTreeMap::new();
+        let mut printed = 0;
+        util::parallel(
+            self.jobs.get(),
+            pairs.len(),
+            || Dijkstra::new(&data.graph),
+            |dijkstra, i| {
+                let pair = &pairs[i];
+                match (nodes[&pair.start], nodes[&pair.goal]) {
+                    (None, _) => Outcome::NotFound(pair.start.clone()),
+                    (_, None) => Outcome::NotFound(pair.goal.clone()),
+                    (Some(start), Some(goal)) => {
+                        find(dijkstra, data, landmarks.as_ref(), start, goal)
+                    }
+                }
+            },
+            |i, outcome| -> Result<()> {
+                pending.insert(i, outcome);
+                while let Some(outcome) = pending.remove(&printed) {
+                    print_outcome(&mut out, data, format, &pairs[printed], outcome)?;
+                    printed += 1;
+                    bar.inc(1);
+                }
+                out.flush()?;
+                Ok(())
+            },
+        )?;
+
+        bar.finish_and_clear();
+        Ok(())
+    }
+}
@@ -1,22 +1,23 @@
-use std::{
-    collections::{BTreeSet, HashMap, HashSet},
-    io::{self, BufWriter},
-};
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use serde_json::{json, Value};
 
 use crate::{
     data::Data,
+    error::Result,
     graph::NodeIdx,
+    output::{self, Csv, Format},
     util::{self, TitleNormalizer},
 };
 
-struct PageMap(Vec<NodeIdx>);
+pub struct PageMap(Vec<NodeIdx>);
 
 impl PageMap {
     fn new(len: usize) -> Self {
         Self(vec![NodeIdx::NONE; len])
     }
 
-    fn get(&self, node: NodeIdx) -> NodeIdx {
+    pub fn get(&self, node: NodeIdx) -> NodeIdx {
         self.0[node.usize()]
     }
 
@@ -25,17 +26,19 @@ impl PageMap {
     }
 }
 
+/// The edges of a node may be sorted by target instead of by position in the
+/// article, so the first link is found by its start.
 fn first_viable_link(data: &Data, node: NodeIdx) -> Option<NodeIdx> {
-    for edge in data.graph.edge_slice(node) {
-        let link = &data.links[edge.usize()];
-        if !link.in_parens() && !link.in_structure() {
-            return Some(*edge);
-        }
-    }
-    None
+    data.graph
+        .edge_targets(node)
+        .map(|(edge, target)| (data.links.get(edge), target))
+        .filter(|(link, _)| !link.in_parens() && !link.in_structure())
+        .min_by_key(|(link, _)| link.start)
+        .map(|(_, target)| target)
 }
 
-fn find_forward_edges(data: &Data) -> PageMap {
+/// Map every page to the target of its first viable link.
+pub fn find_forward_edges(data: &Data) -> PageMap {
     let mut result = PageMap::new(data.pages.len());
     for node in data.graph.nodes() {
         if let Some(first_link) = first_viable_link(data, node) {
@@ -113,76 +116,184 @@ fn resolve_clusters(forward: &PageMap, cluster: &PageMap) -> HashMap<NodeIdx, Cl
     result
 }
 
-fn print_forward_edges_as_json(data: &Data, forward: &PageMap) -> io::Result<()> {
-    let map = forward
-        .0
-        .iter()
-        .enumerate()
-        .map(|(node, first_link)| {
-            let page_title = &data.pages[node].title;
-            let first_link_title = if *first_link == NodeIdx::NONE {
-                None
+/// Text and JSON both print a JSON object mapping titles to first links.
+fn print_forward_edges(data: &Data, forward: &PageMap, format: Format) -> Result<()> {
+    let first_links = forward.0.iter().enumerate().map(|(node, first_link)| {
+        let page_title = data.pages.title(NodeIdx::new(node));
+        let first_link_title = if *first_link == NodeIdx::NONE {
+            None
+        } else {
+            Some(data.pages.title(*first_link))
+        };
+        (page_title, first_link_title)
+    });
+
+    if format == Format::Csv {
+        let mut csv = Csv::new(&["title", "first_link"])?;
+        for (page_title, first_link_title) in first_links {
+            csv.row(&[&page_title, &first_link_title.unwrap_or_default()])?;
+        }
+        csv.finish()?;
+    } else {
+        output::print_json(&first_links.collect::<HashMap<_, _>>())?;
+    }
+    Ok(())
+}
+
+fn print_trace(
+    normalizer: &TitleNormalizer,
+    data: &Data,
+    forward: &PageMap,
+    start: &str,
+    format: Format,
+) -> Result<()> {
+    let start_idx = util::resolve_title(normalizer, data, start)?;
+    let (pages, end) = trace(forward, start_idx);
+
+    match format {
+        Format::Text => {
+            print_pages(data, &pages);
+            if end == NodeIdx::NONE {
+                println!("> dead-end reached");
             } else {
-                Some(&data.pages[first_link.usize()].title)
+                let title = data.pages.title(end);
+                println!("> loop detected ({title})");
+            }
+        }
+        Format::Json => {
+            let end = if end == NodeIdx::NONE {
+                json!({ "kind": "dead-end" })
+            } else {
+                json!({ "kind": "loop", "page": output::page(data, end) })
             };
-            (page_title, first_link_title)
-        })
-        .collect::<HashMap<_, _>>();
+            let result = json!({ "pages": output::pages(data, pages), "end": end });
+            output::print_json(&result)?;
+        }
+        Format::Csv => {
+            let mut csv = Csv::new(&["step", "title", "redirect"])?;
+            for (step, node) in pages.into_iter().enumerate() {
+                let page = data.pages.get(node);
+                csv.row(&[&step, &page.title, &page.redirect])?;
+            }
+            csv.finish()?;
+        }
+    }
 
-    let writer = BufWriter::new(io::stdout());
-    serde_json::to_writer_pretty(writer, &map)?;
     Ok(())
 }
 
-fn print_trace(normalizer: &TitleNormalizer, data: &Data, forward: &PageMap, start: &str) {
-    let start_idx = util::resolve_title(normalizer, data, start);
-
-    let mut current = start_idx;
-    let mut visited = HashSet::new();
-    loop {
-        let page = &data.pages[current.usize()];
+fn print_pages(data: &Data, pages: &[NodeIdx]) {
+    for node in pages {
+        let page = data.pages.get(*node);
         let title = &page.title;
         if page.redirect {
             println!("  v {title}");
         } else {
             println!("  - {title}");
         }
+    }
+}
 
+/// Follow the first links starting at a page.
+///
+/// Returns the visited pages and the page where a loop was detected, or
+/// [`NodeIdx::NONE`] if a dead-end was reached.
+pub fn trace(forward: &PageMap, start: NodeIdx) -> (Vec<NodeIdx>, NodeIdx) {
+    let mut current = start;
+    let mut pages = vec![];
+    let mut visited = HashSet::new();
+    loop {
+        pages.push(current);
         visited.insert(current);
 
         let next = forward.get(current);
-
-        if next == NodeIdx::NONE {
-            println!("> dead-end reached");
-            return;
-        }
-
-        if visited.contains(&next) {
-            let page = &data.pages[next.usize()];
-            let title = &page.title;
-            println!("> loop detected ({title})");
-            return;
+        if next == NodeIdx::NONE || visited.contains(&next) {
+            return (pages, next);
         }
 
         current = next;
     }
 }
 
-fn print_canonical_pages_as_json(data: &Data, cluster: &PageMap) -> io::Result<()> {
-    let map = cluster
-        .0
-        .iter()
-        .enumerate()
-        .map(|(page, canonical)| {
-            (
-                &data.pages[page].title,
-                &data.pages[canonical.usize()].title,
-            )
-        })
-        .collect::<HashMap<_, _>>();
-
-    let writer = BufWriter::new(io::stdout());
-    serde_json::to_writer_pretty(writer, &map)?;
+/// Text and JSON both print a JSON object mapping titles to canonical pages.
+fn print_canonical_pages(data: &Data, cluster: &PageMap, format: Format) -> Result<()> {
+    let canonical_pages = cluster.0.iter().enumerate().map(|(page, canonical)| {
+        (
+            data.pages.title(NodeIdx::new(page)),
+            data.pages.title(*canonical),
+        )
+    });
+
+    if format == Format::Csv {
+        let mut csv = Csv::new(&["title", "canonical"])?;
+        for (title, canonical) in canonical_pages {
+            csv.row(&[&title, &canonical])?;
+        }
+        csv.finish()?;
+    } else {
+        output::print_json(&canonical_pages.collect::<HashMap<_, _>>())?;
+    }
+    Ok(())
+}
+
+fn print_clusters(
+    data: &Data,
+    resolved: &HashMap<NodeIdx, Cluster>,
+    cluster_by_size: Vec<(NodeIdx, u32)>,
+    format: Format,
+) -> Result<()> {
+    let clusters = cluster_by_size
+        .into_iter()
+        .map(|(canonical, size)| (resolved.get(&canonical).unwrap(), size));
+
+    match format {
+        Format::Text => {
+            for (cluster, size) in clusters {
+                match cluster {
+                    Cluster::DeadEnd(page) => {
+                        let title = data.pages.title(*page);
+                        println!("Cluster (dead-end, {size}): {title}");
+                    }
+                    Cluster::Loop(pages) => {
+                        println!("Cluster ({}-loop, {size}):", pages.len());
+                        print_pages(data, pages);
+                    }
+                }
+            }
+        }
+        Format::Json => {
+            let clusters = clusters
+                .map(|(cluster, size)| match cluster {
+                    Cluster::DeadEnd(page) => json!({
+                        "kind": "dead-end",
+                        "size": size,
+                        "pages": output::pages(data, [*page]),
+                    }),
+                    Cluster::Loop(pages) => json!({
+                        "kind": "loop",
+                        "size": size,
+                        "pages": output::pages(data, pages.iter().copied()),
+                    }),
+                })
+                .collect::<Value>();
+            output::print_json(&clusters)?;
+        }
+        Format::Csv => {
+            let mut csv = Csv::new(&["cluster", "kind", "size", "title", "redirect"])?;
+            for (i, (cluster, size)) in clusters.enumerate() {
+                let (kind, pages) = match cluster {
+                    Cluster::DeadEnd(page) => ("dead-end", std::slice::from_ref(page)),
+                    Cluster::Loop(pages) => ("loop", &pages[..]),
+                };
+                for node in pages {
+                    let page = data.pages.get(*node);
+                    csv.row(&[&i, &kind, &size, &page.title, &page.redirect])?;
+                }
+            }
+            csv.finish()?;
+        }
+    }
+
     Ok(())
 }
 
@@ -202,21 +313,21 @@ pub struct Cmd {
 }
 
 impl Cmd {
-    pub fn run(self, data: Data) -> io::Result<()> {
+    pub fn run(self, data: &Data, format: Format) -> Result<()> {
         let normalizer = TitleNormalizer::new();
 
         eprintln!(">> Forward");
-        let forward = find_forward_edges(&data);
+        let forward = find_forward_edges(data);
 
         match self.command {
             Command::First => {
                 eprintln!(">> First links");
-                print_forward_edges_as_json(&data, &forward)?;
+                print_forward_edges(data, &forward, format)?;
                 return Ok(());
             }
             Command::Trace { start } => {
                 eprintln!(">> Tracing");
-                print_trace(&normalizer, &data, &forward, &start);
+                print_trace(&normalizer, data, &forward, &start, format)?;
                 return Ok(());
             }
             _ => {}
@@ -226,10 +337,10 @@ impl Cmd {
         // canonical page of a cluster is either a dead-end or the loop member with
         // the smallest index.
         eprintln!(">> Find clusters");
-        let cluster = find_clusters(&data, &forward);
+        let cluster = find_clusters(data, &forward);
 
         if self.command == Command::Canonical {
-            print_canonical_pages_as_json(&data, &cluster)?;
+            print_canonical_pages(data, &cluster, format)?;
             return Ok(());
         }
 
@@ -237,7 +348,11 @@ impl Cmd {
         eprintln!(">> Measure clusters");
         let mut cluster_size = HashMap::<NodeIdx, u32>::new();
         for (i, canonical) in cluster.0.iter().enumerate() {
-            assert!(*canonical != NodeIdx::NONE, "{}", data.pages[i].title);
+            assert!(
+                *canonical != NodeIdx::NONE,
+                "{}",
+                data.pages.title(NodeIdx::new(i))
+            );
             *cluster_size.entry(*canonical).or_default() += 1;
         }
         let mut cluster_by_size = cluster_size.into_iter().collect::<Vec<_>>();
@@ -247,26 +362,7 @@ impl Cmd {
         // Print clusters
         assert!(self.command == Command::Cluster);
         let resolved = resolve_clusters(&forward, &cluster);
-        for (canonical, size) in cluster_by_size {
-            match resolved.get(&canonical).unwrap() {
-                Cluster::DeadEnd(page) => {
-                    let title = &data.pages[page.usize()].title;
-                    println!("Cluster (dead-end, {size}): {title}");
-                }
-                Cluster::Loop(pages) => {
-                    println!("Cluster ({}-loop, {size}):", pages.len());
-                    for page in pages {
-                        let page = &data.pages[page.usize()];
-                        let title = &page.title;
-                        if page.redirect {
-                            println!("  v {title}");
-                        } else {
-                            println!("  - {title}");
-                        }
-                    }
-                }
-            }
-        }
+        print_clusters(data, &resolved, cluster_by_size, format)?;
 
         Ok(())
     }
@@ -0,0 +1,227 @@
+use std::{cell::RefCell, io, path::PathBuf};
+
+use clap::Parser;
+use rustyline::{
+    completion::{Completer, Pair},
+    error::ReadlineError,
+    highlight::Highlighter,
+    hint::Hinter,
+    history::DefaultHistory,
+    validate::Validator,
+    Context, Editor, Helper,
+};
+
+use crate::{
+    commands::{longest_path, path, pg, show, stats, titles},
+    data::Data,
+    error::Result,
+    output::Format,
+    util::TitleNormalizer,
+};
+
+/// Maximum number of titles offered when completing.
+const MAX_COMPLETIONS: usize = 100;
+
+/// A single line entered into the REPL.
+#[derive(Debug, Parser)]
+#[command(no_binary_name = true)]
+enum Line {
+    Show(show::Cmd),
+    Titles(titles::Cmd),
+    Stats(stats::Cmd),
+    Path(path::Cmd),
+    LongestPath(longest_path::Cmd),
+    Pg(pg::Cmd),
+    /// Leave the REPL.
+    #[command(alias = "quit")]
+    Exit,
+}
+
+impl Line {
+    /// Whether the command needs the in-edges of the graph.
+    fn in_edges(&self) -> bool {
+        match self {
+            Self::Path(cmd) => cmd.in_edges(),
+            _ => false,
+        }
+    }
+}
+
+/// Completes article titles.
+struct TitleCompleter<'a> {
+    data: &'a RefCell<Data>,
+    normalizer: TitleNormalizer,
+}
+
+impl TitleCompleter<'_> {
+    fn titles(&self, prefix: &str) -> Vec<String> {
+        let data = self.data.borrow();
+        match &data.title_index {
+            Some(index) => index
+                .prefix(&self.normalizer, prefix)
+                .take(MAX_COMPLETIONS)
+                .map(|node| data.pages.title(node).to_string())
+                .collect(),
+            None => data
+                .pages
+                .iter()
+                .map(|p| p.title)
+                .filter(|title| title.starts_with(prefix))
+                .take(MAX_COMPLETIONS)
+                .map(|title| title.to_string())
+                .collect(),
+        }
+    }
+}
+
+/// Find where the last argument of a line starts and whether it is quoted.
+fn last_arg(line: &str) -> (usize, bool) {
+    let mut start = 0;
+    let mut quoted = false;
+    for (i, c) in line.char_indices() {
+        if c == '"' {
+            quoted = !quoted;
+            start = i + 1;
+        } else if c.is_whitespace() && !quoted {
+            start = i + c.len_utf8();
+        }
+    }
+    (start, quoted)
+}
+
+impl Completer for TitleCompleter<'_> {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let (start, quoted) = last_arg(&line[..pos]);
+        let prefix = &line[start..pos];
+        if prefix.is_empty() {
+            return Ok((pos, vec![]));
+        }
+
+        let candidates = self
+            .titles(prefix)
+            .into_iter()
+            .map(|title| {
+                let replacement = if quoted {
+                    format!("{title}\"")
+                } else if title.contains(|c: char| c.is_whitespace() || c == '"') {
+                    format!("\"{title}\"")
+                } else {
+                    title.to_string()
+                };
+                Pair {
+                    display: title,
+                    replacement,
+                }
+            })
+            .collect();
+
+        Ok((start, candidates))
+    }
+}
+
+impl Hinter for TitleCompleter<'_> {
+    type Hint = String;
+}
+
+impl Highlighter for TitleCompleter<'_> {}
+
+impl Validator for TitleCompleter<'_> {}
+
+impl Helper for TitleCompleter<'_> {}
+
+fn readline_error(err: ReadlineError) -> io::Error {
+    match err {
+        ReadlineError::Io(err) => err,
+        err => io::Error::other(err),
+    }
+}
+
+/// Answer queries line by line without reloading the data.
+///
+/// Accepts the show, titles, stats, path, longest-path and pg commands, which
+/// print their results in the global format. Titles can be completed with tab.
+/// The in-edges are collected the first time a command needs them.
+#[derive(Debug, clap::Parser)]
+pub struct Cmd {
+    /// Load and save the input history from and to this file.
+    #[arg(long)]
+    history: Option<PathBuf>,
+}
+
+impl Cmd {
+    pub fn run(self, data: Data, format: Format) -> Result<()> {
+        let data = RefCell::new(data);
+        let mut editor =
+            Editor::<TitleCompleter<'_>, DefaultHistory>::new().map_err(readline_error)?;
+        editor.set_helper(Some(TitleCompleter {
+            data: &data,
+            normalizer: TitleNormalizer::new(),
+        }));
+
+        if let Some(history) = &self.history {
+            // The file doesn't exist yet when the REPL is used for the first time.
+            let _ = editor.load_history(history);
+        }
+
+        loop {
+            let line = match editor.readline("brood> ") {
+                Ok(line) => line,
+                Err(ReadlineError::Interrupted) => continue,
+                Err(ReadlineError::Eof) => break,
+                Err(err) => return Err(readline_error(err).into()),
+            };
+
+            if line.trim().is_empty() {
+                continue;
+            }
+            editor.add_history_entry(&line).map_err(readline_error)?;
+
+            let Some(words) = shlex::split(&line) else {
+                eprintln!("Error: unbalanced quotes");
+                continue;
+            };
+
+            let line = match Line::try_parse_from(words) {
+                Ok(Line::Exit) => break,
+                Ok(line) => line,
+                Err(err) => {
+                    let _ = err.print();
+                    continue;
+                }
+            };
+
+            if line.in_edges() && data.borrow().graph.reverse.is_none() {
+                eprintln!("> Collecting in-edges");
+                data.borrow_mut().graph.build_reverse();
+            }
+
+            let data = &data.borrow();
+            let result = match line {
+                Line::Show(cmd) => cmd.run(data, format),
+                Line::Titles(cmd) => cmd.run(data, format),
+                Line::Stats(cmd) => cmd.run(data, format),
+                Line::Path(cmd) => cmd.run(data, format),
+                Line::LongestPath(cmd) => cmd.run(data, format),
+                Line::Pg(cmd) => cmd.run(data, format),
+                Line::Exit => unreachable!(),
+            };
+
+            if let Err(err) = result {
+                eprintln!("Error: {err}");
+            }
+        }
+
+        if let Some(history) = &self.history {
+            editor.save_history(history).map_err(readline_error)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,315 @@
+use std::{cmp::Reverse, collections::HashMap, io};
+
+use serde_json::{json, Value};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::{
+    commands::{
+        path,
+        pg::{self, PageMap},
+        stats::degrees,
+    },
+    data::Data,
+    error::Error,
+    graph::NodeIdx,
+    output,
+    util::{self, TitleNormalizer},
+};
+
+/// An error response.
+struct HttpError {
+    status: u16,
+    body: Value,
+}
+
+impl HttpError {
+    fn new(status: u16, msg: impl Into<String>) -> Self {
+        Self {
+            status,
+            body: json!({ "error": msg.into() }),
+        }
+    }
+}
+
+impl From<Error> for HttpError {
+    fn from(value: Error) -> Self {
+        match value {
+            Error::ArticleNotFound { title, suggestions } => Self {
+                status: 404,
+                body: json!({
+                    "error": format!("article not found: {title:?}"),
+                    "suggestions": suggestions,
+                }),
+            },
+            value => Self::new(500, value.to_string()),
+        }
+    }
+}
+
+type HttpResult = Result<Value, HttpError>;
+
+fn percent_decode(s: &str) -> String {
+    let mut bytes = vec![];
+    let mut rest = s.as_bytes();
+    while let Some((byte, tail)) = rest.split_first() {
+        match byte {
+            b'+' => bytes.push(b' '),
+            b'%' if tail.len() >= 2 => {
+                let hex = str::from_utf8(&tail[..2]).ok();
+                match hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                    Some(decoded) => {
+                        bytes.push(decoded);
+                        rest = &tail[2..];
+                        continue;
+                    }
+                    None => bytes.push(b'%'),
+                }
+            }
+            byte => bytes.push(*byte),
+        }
+        rest = tail;
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+struct Query(HashMap<String, String>);
+
+impl Query {
+    fn parse(query: &str) -> Self {
+        let params = query
+            .split('&')
+            .filter(|p| !p.is_empty())
+            .map(|p| match p.split_once('=') {
+                Some((k, v)) => (percent_decode(k), percent_decode(v)),
+                None => (percent_decode(p), String::new()),
+            })
+            .collect();
+        Self(params)
+    }
+
+    fn get(&self, name: &str) -> Result<&str, HttpError> {
+        self.0
+            .get(name)
+            .map(|v| v.as_str())
+            .ok_or_else(|| HttpError::new(400, format!("missing parameter {name:?}")))
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.0
+            .get(name)
+            .is_some_and(|v| v.is_empty() || v == "true" || v == "1")
+    }
+
+    fn number(&self, name: &str, default: usize) -> Result<usize, HttpError> {
+        match self.0.get(name) {
+            Some(v) => v
+                .parse()
+                .map_err(|_| HttpError::new(400, format!("parameter {name:?} must be a number"))),
+            None => Ok(default),
+        }
+    }
+}
+
+struct Api<'a> {
+    data: &'a Data,
+    normalizer: TitleNormalizer,
+    forward: PageMap,
+    outdegree: Vec<usize>,
+    indegree: Vec<usize>,
+    /// All nodes, most outlinks first.
+    most_outlinks: Vec<NodeIdx>,
+    /// All nodes, most inlinks first.
+    most_inlinks: Vec<NodeIdx>,
+}
+
+impl<'a> Api<'a> {
+    /// Requires the in-edges.
+    fn new(data: &'a Data) -> Self {
+        eprintln!(">> Forward");
+        let forward = pg::find_forward_edges(data);
+
+        eprintln!(">> Degrees");
+        let outdegree = degrees::outdegrees(data);
+        let indegree = degrees::indegrees(data);
+        let ranking = |degree: &[usize]| {
+            let mut nodes = data.graph.nodes().collect::<Vec<_>>();
+            nodes.sort_by_key(|n| Reverse(degree[n.usize()]));
+            nodes
+        };
+        let most_outlinks = ranking(&outdegree);
+        let most_inlinks = ranking(&indegree);
+
+        Self {
+            data,
+            normalizer: TitleNormalizer::new(),
+            forward,
+            outdegree,
+            indegree,
+            most_outlinks,
+            most_inlinks,
+        }
+    }
+
+    /// Unlike in the CLI, the user is never asked to pick a suggestion.
+    /// Without a title index, suggesting titles would compare every title, so
+    /// none are suggested.
+    fn locate(&self, title: &str) -> Result<NodeIdx, HttpError> {
+        let node = util::find_title(&self.normalizer, self.data, title);
+        node.ok_or_else(|| match self.data.title_index {
+            Some(_) => util::title_not_found(&self.normalizer, self.data, title).into(),
+            None => Error::ArticleNotFound {
+                title: title.to_string(),
+                suggestions: vec![],
+            }
+            .into(),
+        })
+    }
+
+    fn resolve(&self, title: &str) -> Result<NodeIdx, HttpError> {
+        Ok(util::resolve_redirects(self.data, self.locate(title)?))
+    }
+
+    fn article(&self, query: &Query) -> HttpResult {
+        let node = self.locate(query.get("title")?)?;
+        let page = self.data.pages.get(node);
+        let outlinks = self.data.graph.edge_range(node).len();
+        let inlinks = self.data.graph.in_edge_range(node).len();
+        Ok(json!({
+            "title": page.title,
+            "normalized": self.normalizer.normalize(page.title),
+            "redirect": page.redirect,
+            "redirect_target": self.data.redirect_target(node).map(|n| output::page(self.data, n)),
+            "id": page.id,
+            "length": page.length,
+            "outlinks": outlinks,
+            "inlinks": inlinks,
+        }))
+    }
+
+    fn neighbours(&self, query: &Query) -> HttpResult {
+        let node = self.locate(query.get("title")?)?;
+        let limit = query.number("limit", 100)?;
+        let outlinks = self.data.graph.targets(node).collect::<Vec<_>>();
+        let inlinks = self.data.graph.in_edges(node).map(|(_, source)| source);
+        Ok(json!({
+            "page": output::page(self.data, node),
+            "outlink_count": outlinks.len(),
+            "inlink_count": self.data.graph.in_edge_range(node).len(),
+            "outlinks": output::pages(self.data, outlinks.into_iter().take(limit)),
+            "inlinks": output::pages(self.data, inlinks.take(limit)),
+        }))
+    }
+
+    fn path(&self, start: NodeIdx, goal: NodeIdx) -> Value {
+        output::path(self.data, path::search_path(self.data, start, goal))
+    }
+
+    fn paths(&self, query: &Query) -> HttpResult {
+        let start = self.resolve(query.get("start")?)?;
+        let goal = self.resolve(query.get("goal")?)?;
+
+        let mut result = json!({
+            "start": output::page(self.data, start),
+            "goal": output::page(self.data, goal),
+            "forward": self.path(start, goal),
+        });
+        if query.flag("bidi") {
+            result["backward"] = self.path(goal, start);
+        }
+        Ok(result)
+    }
+
+    fn trace(&self, query: &Query) -> HttpResult {
+        let start = self.resolve(query.get("start")?)?;
+        let (pages, end) = pg::trace(&self.forward, start);
+        let end = if end == NodeIdx::NONE {
+            json!({ "kind": "dead-end" })
+        } else {
+            json!({ "kind": "loop", "page": output::page(self.data, end) })
+        };
+        Ok(json!({ "pages": output::pages(self.data, pages), "end": end }))
+    }
+
+    fn degrees(&self, query: &Query) -> HttpResult {
+        let top = query.number("top", 10)?;
+        let ranking = |nodes: &[NodeIdx]| -> Value {
+            nodes
+                .iter()
+                .take(top)
+                .map(|n| {
+                    let mut page = output::page(self.data, *n);
+                    page["outdegree"] = self.outdegree[n.usize()].into();
+                    page["indegree"] = self.indegree[n.usize()].into();
+                    page
+                })
+                .collect()
+        };
+
+        Ok(json!({
+            "most_outlinks": ranking(&self.most_outlinks),
+            "most_inlinks": ranking(&self.most_inlinks),
+        }))
+    }
+
+    fn handle(&self, request: &Request) -> HttpResult {
+        if *request.method() != Method::Get {
+            return Err(HttpError::new(405, "only GET is supported"));
+        }
+
+        let (path, query) = request.url().split_once('?').unwrap_or((request.url(), ""));
+        let query = Query::parse(query);
+        match path {
+            "/article" => self.article(&query),
+            "/neighbours" => self.neighbours(&query),
+            "/path" => self.paths(&query),
+            "/trace" => self.trace(&query),
+            "/degrees" => self.degrees(&query),
+            _ => Err(HttpError::new(404, format!("unknown endpoint {path:?}"))),
+        }
+    }
+}
+
+/// Answer queries over HTTP, returning JSON.
+///
+/// Only listens on localhost. Endpoints:
+///
+/// - `/article?title=...` shows an article, like the show command
+/// - `/neighbours?title=...[&limit=...]` lists up to 100 (or `limit`) of an
+///   article's outlinks and inlinks
+/// - `/path?start=...&goal=...[&bidi]` finds the shortest path
+/// - `/trace?start=...` follows the first links, like `pg trace`
+/// - `/degrees[?top=...]` ranks articles by out- and indegree
+#[derive(Debug, clap::Parser)]
+pub struct Cmd {
+    #[arg(long, short, default_value_t = 8080)]
+    port: u16,
+}
+
+impl Cmd {
+    pub fn run(self, data: &Data) -> crate::error::Result<()> {
+        let api = Api::new(data);
+
+        let addr = ("127.0.0.1", self.port);
+        let server = Server::http(addr).map_err(io::Error::other)?;
+        eprintln!(">> Listening on http://127.0.0.1:{}", self.port);
+
+        let content_type = Header::from_bytes("Content-Type", "application/json").unwrap();
+        for request in server.incoming_requests() {
+            let (status, body) = match api.handle(&request) {
+                Ok(body) => (200, body),
+                Err(err) => (err.status, err.body),
+            };
+            eprintln!("> {} {} {status}", request.method(), request.url());
+
+            let response = Response::from_string(body.to_string())
+                .with_status_code(status)
+                .with_header(content_type.clone());
+            if let Err(err) = request.respond(response) {
+                // The client may have gone away, keep serving the others.
+                eprintln!("> Failed to respond: {err}");
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,140 @@
+use std::path::PathBuf;
+
+use regex::Regex;
+use serde_json::{json, Value};
+use thousands::Separable;
+
+use crate::{
+    algo::Dijkstra,
+    commands::path,
+    data::Data,
+    error::Result,
+    graph::NodeIdx,
+    output::{self, Csv, Format},
+    util::{self, TitleNormalizer},
+};
+
+/// Find the shortest path from any article of one set to any article of
+/// another.
+///
+/// Each set is either read from a file with one title per line or made up of
+/// all articles whose title matches a regex. Redirects are resolved.
+#[derive(Debug, clap::Parser)]
+pub struct Cmd {
+    /// Start from the articles listed in this file.
+    #[arg(long, value_name = "FILE", required_unless_present = "from")]
+    from_file: Option<PathBuf>,
+
+    /// Start from the articles whose title matches this regex.
+    #[arg(long, value_name = "REGEX", conflicts_with = "from_file")]
+    from: Option<Regex>,
+
+    /// Stop at the articles listed in this file.
+    #[arg(long, value_name = "FILE", required_unless_present = "to")]
+    to_file: Option<PathBuf>,
+
+    /// Stop at the articles whose title matches this regex.
+    #[arg(long, value_name = "REGEX", conflicts_with = "to_file")]
+    to: Option<Regex>,
+}
+
+fn matching_titles(data: &Data, regex: &Regex) -> Vec<NodeIdx> {
+    data.pages
+        .iter()
+        .enumerate()
+        .filter(|(_, page)| regex.is_match(page.title))
+        .map(|(i, _)| util::resolve_redirects(data, NodeIdx::new(i)))
+        .collect()
+}
+
+fn resolve_set(
+    normalizer: &TitleNormalizer,
+    data: &Data,
+    file: &Option<PathBuf>,
+    regex: &Option<Regex>,
+) -> Result<Vec<NodeIdx>> {
+    let mut nodes = match (file, regex) {
+        (Some(file), _) => util::resolve_title_file(normalizer, data, file)?,
+        (None, Some(regex)) => matching_titles(data, regex),
+        (None, None) => unreachable!("clap requires one of them"),
+    };
+    nodes.sort();
+    nodes.dedup();
+    Ok(nodes)
+}
+
+impl Cmd {
+    pub fn run(self, data: &Data, format: Format) -> Result<()> {
+        let normalizer = TitleNormalizer::new();
+
+        eprintln!(">> Resolve articles");
+        let starts = resolve_set(&normalizer, data, &self.from_file, &self.from)?;
+        let goals = resolve_set(&normalizer, data, &self.to_file, &self.to)?;
+        eprintln!(
+            "> {} start articles",
+            starts.len().separate_with_underscores()
+        );
+        eprintln!(
+            "> {} goal articles",
+            goals.len().separate_with_underscores()
+        );
+
+        let mut is_goal = vec![false; data.pages.len()];
+        for goal in &goals {
+            is_goal[goal.usize()] = true;
+        }
+
+        eprintln!(">> Find path");
+        let mut dijkstra = Dijkstra::new(&data.graph);
+        let found = dijkstra
+            .run_multi(
+                starts.iter().copied(),
+                |node| is_goal[node.usize()],
+                |source, _edge, _target| !data.pages.redirect(source) as u32,
+            )
+            .map(|goal| {
+                let path = dijkstra.path(goal);
+                (path[0], goal, (dijkstra.cost(goal), path))
+            });
+
+        match format {
+            Format::Text => {
+                println!("Starts: {}", starts.len().separate_with_underscores());
+                println!("Goals:  {}", goals.len().separate_with_underscores());
+                println!();
+                match found {
+                    Some((start, goal, path)) => path::print_path(data, start, goal, Some(path)),
+                    None => println!("No path found from any start to any goal"),
+                }
+            }
+            Format::Json => {
+                let (start, goal, path) = match found {
+                    Some((start, goal, path)) => (
+                        output::page(data, start),
+                        output::page(data, goal),
+                        output::path(data, Some(path)),
+                    ),
+                    None => (Value::Null, Value::Null, Value::Null),
+                };
+                output::print_json(&json!({
+                    "starts": starts.len(),
+                    "goals": goals.len(),
+                    "start": start,
+                    "goal": goal,
+                    "path": path,
+                }))?;
+            }
+            Format::Csv => {
+                let mut csv = Csv::new(output::PATH_HEADER)?;
+                match found {
+                    Some((start, goal, path)) => csv.path_rows(data, start, goal, Some(path))?,
+                    // Like `path_rows` without a path, but no start or goal either.
+                    None => csv.row(&[&"", &"", &"", &"", &"", &"", &""])?,
+                }
+                csv.finish()?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -1,13 +1,22 @@
-use std::{collections::HashSet, io};
+use std::collections::HashSet;
 
+use serde_json::{json, Value};
 use thousands::Separable;
 
 use crate::{
     data::Data,
+    error::Result,
+    graph::NodeIdx,
+    output::{self, Csv, Format},
     util::{self, TitleNormalizer},
 };
 
 /// Show info about a specific article.
+///
+/// Redirects are followed, so info is shown for every page of the redirect
+/// chain. A redirect loop ends the chain at its first repeated page, which
+/// JSON marks with a final `{"kind": "loop", "page": ...}` entry. As CSV, one
+/// row is printed per page, or one row per linked page if `--links` is given.
 #[derive(Debug, clap::Parser)]
 pub struct Cmd {
     title: String,
@@ -17,135 +26,260 @@ pub struct Cmd {
     links: bool,
 }
 
+/// The links of a single page.
+struct PageLinks {
+    node: NodeIdx,
+    outlinks: usize,
+    inlinks: usize,
+    unique_outlinks: usize,
+    unique_inlinks: usize,
+    /// Sorted by title, like the other two lists.
+    twins: Vec<NodeIdx>,
+    outlink_only: Vec<NodeIdx>,
+    inlink_only: Vec<NodeIdx>,
+}
+
+impl PageLinks {
+    fn new(data: &Data, node: NodeIdx) -> Self {
+        let outlinks = data.graph.targets(node).collect::<Vec<_>>();
+        let inlinks = util::inlinks(data, node);
+
+        let outlinks_set = outlinks.iter().copied().collect::<HashSet<_>>();
+        let inlinks_set = inlinks.iter().copied().collect::<HashSet<_>>();
+        let twins_set = outlinks_set
+            .intersection(&inlinks_set)
+            .copied()
+            .collect::<HashSet<_>>();
+
+        let sorted = |nodes: HashSet<NodeIdx>| {
+            let mut nodes = nodes.into_iter().collect::<Vec<_>>();
+            nodes.sort_by_key(|n| data.pages.title(*n));
+            nodes
+        };
+
+        Self {
+            node,
+            outlinks: outlinks.len(),
+            inlinks: inlinks.len(),
+            unique_outlinks: outlinks_set.len(),
+            unique_inlinks: inlinks_set.len(),
+            twins: sorted(twins_set.clone()),
+            outlink_only: sorted(&outlinks_set - &twins_set),
+            inlink_only: sorted(&inlinks_set - &twins_set),
+        }
+    }
+
+    /// The linked pages together with how they are linked.
+    fn relations(&self) -> impl Iterator<Item = (&'static str, NodeIdx)> + '_ {
+        let twins = self.twins.iter().map(|n| ("twin", *n));
+        let outlinks = self.outlink_only.iter().map(|n| ("outlink", *n));
+        let inlinks = self.inlink_only.iter().map(|n| ("inlink", *n));
+        twins.chain(outlinks).chain(inlinks)
+    }
+}
+
 impl Cmd {
-    pub fn run(self, data: Data) -> io::Result<()> {
-        let normalizer = TitleNormalizer::new();
+    fn print_text(&self, normalizer: &TitleNormalizer, data: &Data, links: &PageLinks) {
+        let page = data.pages.get(links.node);
 
-        println!(">> Locate article");
-        let mut node = util::locate_title(&normalizer, &data, &self.title);
+        const W_LABEL: usize = 12;
+        const W_NUM: usize = 11;
 
-        loop {
-            let page = &data.pages[node.usize()];
+        println!();
 
-            const W_LABEL: usize = 12;
-            const W_NUM: usize = 11;
+        println!("{:>W_LABEL$}: {}", "Title", page.title);
 
-            println!();
+        println!(
+            "{:>W_LABEL$}: {}",
+            "Title (norm)",
+            normalizer.normalize(page.title)
+        );
 
-            println!("{:>W_LABEL$}: {}", "Title", page.title);
+        println!("{:>W_LABEL$}: {}", "Redirect", page.redirect);
 
-            println!(
-                "{:>W_LABEL$}: {}",
-                "Title (norm)",
-                normalizer.normalize(&page.title)
-            );
+        println!("{:>W_LABEL$}: {:>W_NUM$}", "ID", page.id);
 
-            println!("{:>W_LABEL$}: {}", "Redirect", page.redirect);
+        println!(
+            "{:>W_LABEL$}: {:>W_NUM$}",
+            "Length",
+            page.length.separate_with_underscores()
+        );
 
-            println!("{:>W_LABEL$}: {:>W_NUM$}", "ID", page.id);
+        println!(
+            "{:>W_LABEL$}: {:>W_NUM$}",
+            "Links (out)",
+            links.outlinks.separate_with_underscores()
+        );
 
-            println!(
-                "{:>W_LABEL$}: {:>W_NUM$}",
-                "Length",
-                page.length.separate_with_underscores()
-            );
+        println!(
+            "{:>W_LABEL$}: {:>W_NUM$}",
+            "unique",
+            links.unique_outlinks.separate_with_underscores()
+        );
 
-            let outlinks = data.graph.edge_slice(node).to_vec();
-            let inlinks = data
-                .graph
-                .edges()
-                .filter(|(_, target)| *target == node)
-                .map(|(source, _)| source)
-                .collect::<Vec<_>>();
-
-            let outlinks_set = outlinks.iter().copied().collect::<HashSet<_>>();
-            let inlinks_set = inlinks.iter().copied().collect::<HashSet<_>>();
-            let twins_set = outlinks_set
-                .intersection(&inlinks_set)
-                .copied()
-                .collect::<HashSet<_>>();
+        println!(
+            "{:>W_LABEL$}: {:>W_NUM$}",
+            "Links (in)",
+            links.inlinks.separate_with_underscores()
+        );
 
-            println!(
-                "{:>W_LABEL$}: {:>W_NUM$}",
-                "Links (out)",
-                outlinks.len().separate_with_underscores()
-            );
+        println!(
+            "{:>W_LABEL$}: {:>W_NUM$}",
+            "unique",
+            links.unique_inlinks.separate_with_underscores()
+        );
 
-            println!(
-                "{:>W_LABEL$}: {:>W_NUM$}",
-                "unique",
-                outlinks_set.len().separate_with_underscores()
-            );
+        println!(
+            "{:>W_LABEL$}: {:>W_NUM$}",
+            "Twins",
+            links.twins.len().separate_with_underscores()
+        );
 
-            println!(
-                "{:>W_LABEL$}: {:>W_NUM$}",
-                "Links (in)",
-                inlinks.len().separate_with_underscores()
-            );
+        if self.links {
+            println!();
+            println!("Twins ({}):", links.twins.len().separate_with_underscores());
+            for node in &links.twins {
+                println!("{}", util::fmt_page(&data.pages.get(*node)));
+            }
 
+            println!();
             println!(
-                "{:>W_LABEL$}: {:>W_NUM$}",
-                "unique",
-                inlinks_set.len().separate_with_underscores()
+                "Only outlinks ({}):",
+                links.outlink_only.len().separate_with_underscores()
             );
+            for node in &links.outlink_only {
+                println!("{}", util::fmt_page(&data.pages.get(*node)));
+            }
 
+            println!();
             println!(
-                "{:>W_LABEL$}: {:>W_NUM$}",
-                "Twins",
-                twins_set.len().separate_with_underscores()
+                "Only inlinks ({}):",
+                links.inlink_only.len().separate_with_underscores()
             );
+            for node in &links.inlink_only {
+                println!("{}", util::fmt_page(&data.pages.get(*node)));
+            }
+        }
+    }
 
-            if self.links {
-                let mut twin_pages = twins_set
-                    .iter()
-                    .map(|n| &data.pages[n.usize()])
-                    .collect::<Vec<_>>();
-
-                let mut outlink_only_pages = outlinks_set
-                    .difference(&twins_set)
-                    .map(|n| &data.pages[n.usize()])
-                    .collect::<Vec<_>>();
-
-                let mut inlink_only_pages = inlinks_set
-                    .difference(&twins_set)
-                    .map(|n| &data.pages[n.usize()])
-                    .collect::<Vec<_>>();
-
-                twin_pages.sort_by_key(|p| &p.title);
-                outlink_only_pages.sort_by_key(|p| &p.title);
-                inlink_only_pages.sort_by_key(|p| &p.title);
+    fn to_json(&self, normalizer: &TitleNormalizer, data: &Data, links: &PageLinks) -> Value {
+        let page = data.pages.get(links.node);
+        let mut result = json!({
+            "title": page.title,
+            "normalized": normalizer.normalize(page.title),
+            "redirect": page.redirect,
+            "id": page.id,
+            "length": page.length,
+            "links_out": links.outlinks,
+            "links_out_unique": links.unique_outlinks,
+            "links_in": links.inlinks,
+            "links_in_unique": links.unique_inlinks,
+            "twins": links.twins.len(),
+        });
+        if self.links {
+            result["twin_pages"] = output::pages(data, links.twins.iter().copied());
+            result["outlink_only_pages"] = output::pages(data, links.outlink_only.iter().copied());
+            result["inlink_only_pages"] = output::pages(data, links.inlink_only.iter().copied());
+        }
+        result
+    }
 
-                println!();
-                println!("Twins ({}):", twin_pages.len().separate_with_underscores());
-                for page in twin_pages {
-                    println!("{}", util::fmt_page(page));
+    fn print_csv(
+        &self,
+        normalizer: &TitleNormalizer,
+        data: &Data,
+        chain: &[PageLinks],
+    ) -> Result<()> {
+        if self.links {
+            let mut csv = Csv::new(&["page", "relation", "title", "redirect"])?;
+            for links in chain {
+                let title = data.pages.title(links.node);
+                for (relation, node) in links.relations() {
+                    let page = data.pages.get(node);
+                    csv.row(&[&title, &relation, &page.title, &page.redirect])?;
                 }
+            }
+            csv.finish()?;
+            return Ok(());
+        }
 
-                println!();
-                println!(
-                    "Only outlinks ({}):",
-                    outlink_only_pages.len().separate_with_underscores()
-                );
-                for page in outlink_only_pages {
-                    println!("{}", util::fmt_page(page));
-                }
+        let mut csv = Csv::new(&[
+            "title",
+            "normalized",
+            "redirect",
+            "id",
+            "length",
+            "links_out",
+            "links_out_unique",
+            "links_in",
+            "links_in_unique",
+            "twins",
+        ])?;
+        for links in chain {
+            let page = data.pages.get(links.node);
+            csv.row(&[
+                &page.title,
+                &normalizer.normalize(page.title),
+                &page.redirect,
+                &page.id,
+                &page.length,
+                &links.outlinks,
+                &links.unique_outlinks,
+                &links.inlinks,
+                &links.unique_inlinks,
+                &links.twins.len(),
+            ])?;
+        }
+        csv.finish()?;
+        Ok(())
+    }
 
-                println!();
-                println!(
-                    "Only inlinks ({}):",
-                    inlink_only_pages.len().separate_with_underscores()
-                );
-                for page in inlink_only_pages {
-                    println!("{}", util::fmt_page(page));
-                }
-            }
+    pub fn run(self, data: &Data, format: Format) -> Result<()> {
+        let normalizer = TitleNormalizer::new();
+
+        eprintln!(">> Locate article");
+        let mut node = util::locate_title(&normalizer, data, &self.title)?;
 
+        let mut chain = vec![];
+        let mut seen = HashSet::new();
+        // The page where the chain loops back, if it does.
+        let mut repeated = None;
+        loop {
+            seen.insert(node);
+            chain.push(PageLinks::new(data, node));
             node = match data.redirect_target(node) {
+                Some(target) if seen.contains(&target) => {
+                    repeated = Some(target);
+                    break;
+                }
                 Some(target) => target,
                 None => break,
             };
         }
 
+        match format {
+            Format::Text => {
+                for links in &chain {
+                    self.print_text(&normalizer, data, links);
+                }
+                if let Some(node) = repeated {
+                    println!();
+                    println!("Redirect loop back to {}", data.pages.title(node));
+                }
+            }
+            Format::Json => {
+                let mut chain = chain
+                    .iter()
+                    .map(|links| self.to_json(&normalizer, data, links))
+                    .collect::<Vec<_>>();
+                if let Some(node) = repeated {
+                    chain.push(json!({ "kind": "loop", "page": output::page(data, node) }));
+                }
+                output::print_json(&Value::from(chain))?;
+            }
+            Format::Csv => self.print_csv(&normalizer, data, &chain)?,
+        }
+
         Ok(())
     }
 }
@@ -1,11 +1,15 @@
-mod degrees;
+pub mod degrees;
 mod redirects;
 
-use std::io;
-
 use thousands::Separable;
 
-use crate::data::Data;
+use serde_json::Map;
+
+use crate::{
+    data::{Data, Load},
+    error::Result,
+    output::{self, Csv, Format},
+};
 
 #[derive(Debug, clap::Parser)]
 enum Command {
@@ -20,78 +24,81 @@ pub struct Cmd {
     command: Option<Command>,
 }
 
+/// Name in CSV and JSON output, label in text output, and value of each stat.
+type Stat = (&'static str, &'static str, usize);
+
+fn print_text(stats: &[Stat]) {
+    const W_LABEL: usize = 14;
+    const W_NUM: usize = 11;
+
+    println!();
+    for (name, label, value) in stats {
+        if *name == "links" {
+            println!();
+        }
+        println!(
+            "{:>W_LABEL$}: {:>W_NUM$}",
+            label,
+            value.separate_with_underscores()
+        );
+    }
+}
+
 impl Cmd {
-    pub fn run(self, data: Data) -> io::Result<()> {
+    pub fn load(&self) -> Load {
+        match self.command {
+            Some(_) => Load::Graph,
+            None => Load::Flags,
+        }
+    }
+
+    pub fn run(self, data: &Data, format: Format) -> Result<()> {
         if let Some(cmd) = self.command {
             return match cmd {
-                Command::Degrees(cmd) => cmd.run(data),
-                Command::Redirects(cmd) => cmd.run(data),
+                Command::Degrees(cmd) => cmd.run(data, format),
+                Command::Redirects(cmd) => cmd.run(data, format),
             };
         }
 
-        println!();
-
-        const W_LABEL: usize = 14;
-        const W_NUM: usize = 11;
-
         let n_pages = data.pages.len();
         let n_redirects = data.pages.iter().filter(|p| p.redirect).count();
         let n_articles = n_pages - n_redirects;
-
-        println!(
-            "{:>W_LABEL$}: {:>W_NUM$}",
-            "Pages",
-            n_pages.separate_with_underscores()
-        );
-
-        println!(
-            "{:>W_LABEL$}: {:>W_NUM$}",
-            "Articles",
-            n_articles.separate_with_underscores()
-        );
-
-        println!(
-            "{:>W_LABEL$}: {:>W_NUM$}",
-            "Redirects",
-            n_redirects.separate_with_underscores()
-        );
-
-        println!();
-        println!(
-            "{:>W_LABEL$}: {:>W_NUM$}",
-            "Links",
-            data.links.len().separate_with_underscores()
-        );
-
-        println!(
-            "{:>W_LABEL$}: {:>W_NUM$}",
-            "in parens",
-            data.links
-                .iter()
-                .filter(|l| l.in_parens())
-                .count()
-                .separate_with_underscores()
-        );
-
-        println!(
-            "{:>W_LABEL$}: {:>W_NUM$}",
-            "in structures",
-            data.links
-                .iter()
-                .filter(|l| l.in_structure())
-                .count()
-                .separate_with_underscores()
-        );
-
-        println!(
-            "{:>W_LABEL$}: {:>W_NUM$}",
-            "pg eligible",
-            data.links
-                .iter()
-                .filter(|l| !l.in_parens() && !l.in_structure())
-                .count()
-                .separate_with_underscores()
-        );
+        let n_links = data.links.len();
+        let n_in_parens = data.links.iter().filter(|l| l.in_parens()).count();
+        let n_in_structure = data.links.iter().filter(|l| l.in_structure()).count();
+        let n_pg_eligible = data
+            .links
+            .iter()
+            .filter(|l| !l.in_parens() && !l.in_structure())
+            .count();
+
+        let stats: [Stat; 7] = [
+            ("pages", "Pages", n_pages),
+            ("articles", "Articles", n_articles),
+            ("redirects", "Redirects", n_redirects),
+            ("links", "Links", n_links),
+            ("in_parens", "in parens", n_in_parens),
+            ("in_structures", "in structures", n_in_structure),
+            ("pg_eligible", "pg eligible", n_pg_eligible),
+        ];
+
+        match format {
+            Format::Text => print_text(&stats),
+            Format::Json => {
+                let stats = stats
+                    .into_iter()
+                    .map(|(name, _, value)| (name.to_string(), value.into()))
+                    .collect::<Map<_, _>>();
+                output::print_json(&stats)?;
+            }
+            Format::Csv => {
+                let mut csv = Csv::new(&["name", "value"])?;
+                for (name, _, value) in stats {
+                    csv.row(&[&name, &value])?;
+                }
+                csv.finish()?;
+            }
+        }
 
         Ok(())
     }
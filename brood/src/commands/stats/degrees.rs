@@ -1,13 +1,37 @@
-use std::{cmp::Reverse, io};
+use std::cmp::Reverse;
 
+use serde_json::{json, Map, Value};
 use thousands::Separable;
 
 use crate::{
-    algo,
     data::{Data, Page},
+    error::Result,
+    output::{self, Csv, Format},
     util,
 };
 
+pub fn outdegrees(data: &Data) -> Vec<usize> {
+    data.graph
+        .nodes()
+        .map(|node| data.graph.edge_range(node).len())
+        .collect()
+}
+
+/// Uses the in-edges if the graph has them.
+pub fn indegrees(data: &Data) -> Vec<usize> {
+    let mut indegree = vec![0; data.pages.len()];
+    if data.graph.reverse.is_some() {
+        for node in data.graph.nodes() {
+            indegree[node.usize()] = data.graph.in_edge_range(node).len();
+        }
+    } else {
+        for (_, target) in data.graph.edges() {
+            indegree[target.usize()] += 1;
+        }
+    }
+    indegree
+}
+
 /// Show stats on article in- and out-degrees.
 #[derive(Debug, clap::Parser)]
 pub struct Cmd {
@@ -15,23 +39,19 @@ pub struct Cmd {
     top: usize,
 }
 
+/// Name in CSV and JSON output, heading in text output, and the top pages of
+/// each ranking.
+type Ranking<'a> = (&'static str, &'static str, Vec<(Page<'a>, usize, usize)>);
+
 impl Cmd {
-    pub fn run(self, mut data: Data) -> io::Result<()> {
-        println!(">> Outdegree");
-        println!("> Counting links");
-        let mut outdegree = vec![usize::MAX; data.pages.len()];
-        for node in data.graph.nodes() {
-            outdegree[node.usize()] = data.graph.edge_range(node).len();
-        }
+    pub fn run(self, data: &Data, format: Format) -> Result<()> {
+        eprintln!(">> Outdegree");
+        eprintln!("> Counting links");
+        let outdegree = outdegrees(data);
 
-        println!(">> Indegree");
-        println!("> Inverting edges");
-        algo::invert(&mut data);
-        let mut indegree = vec![usize::MAX; data.pages.len()];
-        println!("> Counting links");
-        for node in data.graph.nodes() {
-            indegree[node.usize()] = data.graph.edge_range(node).len();
-        }
+        eprintln!(">> Indegree");
+        eprintln!("> Counting links");
+        let indegree = indegrees(data);
 
         let mut by_degrees = data
             .pages
@@ -41,52 +61,97 @@ impl Cmd {
             .map(|((p, od), id)| (p, od, id))
             .collect::<Vec<_>>();
 
-        println!();
-        println!("Most outlinks");
-        println!("¯¯¯¯¯¯¯¯¯¯¯¯¯");
+        let mut rankings = Vec::<Ranking<'_>>::new();
 
         by_degrees.sort_by_key(|(_, od, _)| Reverse(*od));
-        self.print_links(&by_degrees);
-
-        println!();
-        println!("Most inlinks");
-        println!("¯¯¯¯¯¯¯¯¯¯¯¯");
+        rankings.push(("most_outlinks", "Most outlinks", self.top(&by_degrees)));
 
         by_degrees.sort_by_key(|(_, _, id)| Reverse(*id));
-        self.print_links(&by_degrees);
+        rankings.push(("most_inlinks", "Most inlinks", self.top(&by_degrees)));
 
         by_degrees.retain(|(_, od, id)| *od > 0 && *id > 0);
 
-        println!();
-        println!("Most outlinks per non-zero inlink");
-        println!("¯¯¯¯¯¯¯¯¯¯¯¯¯¯¯¯¯¯¯¯¯¯¯¯¯¯¯¯¯¯¯¯¯");
-
         by_degrees.sort_by(|(_, od1, id1), (_, od2, id2)| {
             let r1 = *od1 as f32 / *id1 as f32;
             let r2 = *od2 as f32 / *id2 as f32;
             r2.total_cmp(&r1) // Reverse order so max values are at beginnibg
         });
-        self.print_links(&by_degrees);
-
-        println!();
-        println!("Most inlinks per non-zero outlink");
-        println!("¯¯¯¯¯¯¯¯¯¯¯¯¯¯¯¯¯¯¯¯¯¯¯¯¯¯¯¯¯¯¯¯¯");
+        rankings.push((
+            "most_outlinks_per_inlink",
+            "Most outlinks per non-zero inlink",
+            self.top(&by_degrees),
+        ));
 
         by_degrees.reverse();
-        self.print_links(&by_degrees);
+        rankings.push((
+            "most_inlinks_per_outlink",
+            "Most inlinks per non-zero outlink",
+            self.top(&by_degrees),
+        ));
+
+        match format {
+            Format::Text => {
+                for (_, heading, top) in &rankings {
+                    println!();
+                    println!("{heading}");
+                    println!("{}", "¯".repeat(heading.chars().count()));
+                    print_links(top);
+                }
+            }
+            Format::Json => {
+                let rankings = rankings
+                    .into_iter()
+                    .map(|(name, _, top)| {
+                        let top = top
+                            .into_iter()
+                            .map(|(page, od, id)| {
+                                json!({
+                                    "title": page.title,
+                                    "redirect": page.redirect,
+                                    "outdegree": od,
+                                    "indegree": id,
+                                })
+                            })
+                            .collect::<Value>();
+                        (name.to_string(), top)
+                    })
+                    .collect::<Map<_, _>>();
+                output::print_json(&rankings)?;
+            }
+            Format::Csv => {
+                let mut csv = Csv::new(&[
+                    "ranking",
+                    "rank",
+                    "title",
+                    "redirect",
+                    "outdegree",
+                    "indegree",
+                ])?;
+                for (name, _, top) in rankings {
+                    for (i, (page, od, id)) in top.into_iter().enumerate() {
+                        csv.row(&[&name, &(i + 1), &page.title, &page.redirect, &od, &id])?;
+                    }
+                }
+                csv.finish()?;
+            }
+        }
 
         Ok(())
     }
 
-    fn print_links(&self, by_degrees: &Vec<(&Page, usize, usize)>) {
-        for (i, (page, od, id)) in by_degrees.iter().take(self.top).enumerate() {
-            println!(
-                "{:3}. {} ({} out, {} in)",
-                i + 1,
-                util::fmt_page(page),
-                od.separate_with_underscores(),
-                id.separate_with_underscores()
-            );
-        }
+    fn top<'a>(&self, by_degrees: &[(Page<'a>, usize, usize)]) -> Vec<(Page<'a>, usize, usize)> {
+        by_degrees.iter().take(self.top).copied().collect()
+    }
+}
+
+fn print_links(top: &[(Page<'_>, usize, usize)]) {
+    for (i, (page, od, id)) in top.iter().enumerate() {
+        println!(
+            "{:3}. {} ({} out, {} in)",
+            i + 1,
+            util::fmt_page(page),
+            od.separate_with_underscores(),
+            id.separate_with_underscores()
+        );
     }
 }
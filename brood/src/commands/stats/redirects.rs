@@ -1,14 +1,21 @@
-use std::{cmp::Reverse, collections::HashSet, io};
+use std::{cmp::Reverse, collections::HashSet};
 
+use serde_json::{json, Value};
 use thousands::Separable;
 
-use crate::{data::Data, graph::NodeIdx, util};
+use crate::{
+    data::Data,
+    error::Result,
+    graph::NodeIdx,
+    output::{self, Csv, Format},
+    util,
+};
 
 fn find_redirects(data: &Data) -> Vec<(NodeIdx, NodeIdx, usize)> {
     let mut redirects = Vec::<(NodeIdx, NodeIdx, usize)>::new();
 
     for node in data.graph.nodes() {
-        if !data.pages[node.usize()].redirect {
+        if !data.pages.redirect(node) {
             continue;
         }
 
@@ -19,7 +26,7 @@ fn find_redirects(data: &Data) -> Vec<(NodeIdx, NodeIdx, usize)> {
 
         while let Some(next) = data.redirect_target(curr) {
             if seen.contains(&next) {
-                println!("  Redirect loop: {}", data.pages[node.usize()].title);
+                eprintln!("  Redirect loop: {}", data.pages.title(node));
                 break;
             }
 
@@ -55,6 +62,9 @@ fn follow_redirect(data: &Data, start: NodeIdx) -> Vec<NodeIdx> {
 }
 
 /// Show redirect stats.
+///
+/// As CSV, the stats are printed as name-value rows, or one row per page of
+/// each long redirect chain if `--long` is given.
 #[derive(Debug, clap::Parser)]
 pub struct Cmd {
     /// Show more detailed info.
@@ -63,14 +73,9 @@ pub struct Cmd {
 }
 
 impl Cmd {
-    pub fn run(self, data: Data) -> io::Result<()> {
-        println!(">> Resolve redirects");
-        let redirects = find_redirects(&data);
-
-        println!(
-            "There is a total of {} redirects.",
-            redirects.len().separate_with_underscores()
-        );
+    pub fn run(self, data: &Data, format: Format) -> Result<()> {
+        eprintln!(">> Resolve redirects");
+        let redirects = find_redirects(data);
 
         let mut long = redirects
             .iter()
@@ -78,28 +83,75 @@ impl Cmd {
             .collect::<Vec<_>>();
         long.sort_by_key(|(_, _, l)| Reverse(l));
 
-        println!(
-            "{} redirects take more than one step to reach an article.",
-            long.len().separate_with_underscores()
-        );
-
-        println!(
-            "The longest redirect chain takes {} steps.",
-            long.iter().map(|(_, _, l)| l).max().copied().unwrap_or(0),
-        );
-
-        println!("Though these redirect chains are usually swiftly fixed by bots.");
-
-        if self.long {
-            println!();
-            println!("Redirect chains with length > 1:");
-
-            for (start, _, _) in long {
-                println!();
-                for step in follow_redirect(&data, *start) {
-                    println!("{}", util::fmt_page(&data.pages[step.usize()]));
+        let longest = long.iter().map(|(_, _, l)| l).max().copied().unwrap_or(0);
+
+        let chains = if self.long {
+            long.iter()
+                .map(|(start, _, _)| follow_redirect(data, *start))
+                .collect::<Vec<_>>()
+        } else {
+            vec![]
+        };
+
+        match format {
+            Format::Text => {
+                println!(
+                    "There is a total of {} redirects.",
+                    redirects.len().separate_with_underscores()
+                );
+
+                println!(
+                    "{} redirects take more than one step to reach an article.",
+                    long.len().separate_with_underscores()
+                );
+
+                println!("The longest redirect chain takes {longest} steps.");
+
+                println!("Though these redirect chains are usually swiftly fixed by bots.");
+
+                if self.long {
+                    println!();
+                    println!("Redirect chains with length > 1:");
+
+                    for chain in chains {
+                        println!();
+                        for step in chain {
+                            println!("{}", util::fmt_page(&data.pages.get(step)));
+                        }
+                    }
                 }
             }
+            Format::Json => {
+                let mut result = json!({
+                    "redirects": redirects.len(),
+                    "long_redirects": long.len(),
+                    "longest_chain": longest,
+                });
+                if self.long {
+                    result["chains"] = chains
+                        .into_iter()
+                        .map(|chain| output::pages(data, chain))
+                        .collect::<Value>();
+                }
+                output::print_json(&result)?;
+            }
+            Format::Csv if self.long => {
+                let mut csv = Csv::new(&["chain", "step", "title", "redirect"])?;
+                for (i, chain) in chains.into_iter().enumerate() {
+                    for (step, node) in chain.into_iter().enumerate() {
+                        let page = data.pages.get(node);
+                        csv.row(&[&i, &step, &page.title, &page.redirect])?;
+                    }
+                }
+                csv.finish()?;
+            }
+            Format::Csv => {
+                let mut csv = Csv::new(&["name", "value"])?;
+                csv.row(&[&"redirects", &redirects.len()])?;
+                csv.row(&[&"long_redirects", &long.len()])?;
+                csv.row(&[&"longest_chain", &longest])?;
+                csv.finish()?;
+            }
         }
 
         Ok(())
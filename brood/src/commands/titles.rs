@@ -0,0 +1,70 @@
+use crate::{
+    data::Data,
+    error::Result,
+    graph::NodeIdx,
+    output::{self, Csv, Format},
+    util::{self, TitleNormalizer},
+};
+
+/// List the articles whose title starts with a prefix.
+///
+/// Uses the title index if the datafile has one.
+#[derive(Debug, clap::Parser)]
+pub struct Cmd {
+    prefix: String,
+
+    /// Maximum number of titles to list.
+    #[arg(long, short, default_value_t = 20)]
+    limit: usize,
+}
+
+impl Cmd {
+    pub fn run(self, data: &Data, format: Format) -> Result<()> {
+        let normalizer = TitleNormalizer::new();
+
+        eprintln!(">> Search titles");
+        let nodes = match &data.title_index {
+            Some(index) => index
+                .prefix(&normalizer, &self.prefix)
+                .take(self.limit)
+                .collect::<Vec<_>>(),
+            None => {
+                eprintln!("> No title index, searching all titles");
+                let prefix = normalizer.normalize(&self.prefix);
+                let mut matches = data
+                    .pages
+                    .iter()
+                    .enumerate()
+                    .map(|(i, p)| (normalizer.normalize(p.title), NodeIdx::new(i)))
+                    .filter(|(title, _)| title.starts_with(&prefix))
+                    .collect::<Vec<_>>();
+                matches.sort();
+                matches
+                    .into_iter()
+                    .map(|(_, node)| node)
+                    .take(self.limit)
+                    .collect()
+            }
+        };
+
+        match format {
+            Format::Text => {
+                println!();
+                for node in nodes {
+                    println!("{}", util::fmt_page(&data.pages.get(node)));
+                }
+            }
+            Format::Json => output::print_json(&output::pages(data, nodes))?,
+            Format::Csv => {
+                let mut csv = Csv::new(&["title", "redirect"])?;
+                for node in nodes {
+                    let page = data.pages.get(node);
+                    csv.row(&[&page.title, &page.redirect])?;
+                }
+                csv.finish()?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,124 @@
+use serde_json::{json, Value};
+use thousands::Separable;
+
+use crate::{
+    commands::stats::degrees,
+    data::Data,
+    error::Result,
+    graph::NodeIdx,
+    output::{self, Csv, Format},
+    util,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum Kind {
+    /// Articles without inlinks.
+    Orphans,
+    /// Articles without outlinks.
+    DeadEnds,
+    /// Articles without any links.
+    Isolated,
+}
+
+impl Kind {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Orphans => "orphans",
+            Self::DeadEnds => "dead ends",
+            Self::Isolated => "isolated articles",
+        }
+    }
+}
+
+/// List articles that are missing inlinks, outlinks or both.
+#[derive(Debug, clap::Parser)]
+pub struct Cmd {
+    #[arg(value_enum)]
+    kind: Kind,
+
+    /// Only list redirects, or only articles that aren't redirects.
+    #[arg(long, short)]
+    redirect: Option<bool>,
+
+    /// Only list articles at least this long.
+    #[arg(long, value_name = "LENGTH")]
+    min_length: Option<u32>,
+
+    /// Only list articles at most this long.
+    #[arg(long, value_name = "LENGTH")]
+    max_length: Option<u32>,
+
+    /// Maximum number of articles to list.
+    #[arg(long, short)]
+    limit: Option<usize>,
+}
+
+impl Cmd {
+    pub fn run(self, data: &Data, format: Format) -> Result<()> {
+        eprintln!(">> Count links");
+        let indegrees = degrees::indegrees(data);
+        let outdegrees = degrees::outdegrees(data);
+
+        eprintln!(">> Find {}", self.kind.name());
+        let nodes = data
+            .graph
+            .nodes()
+            .filter(|n| {
+                let (inlinks, outlinks) = (indegrees[n.usize()], outdegrees[n.usize()]);
+                match self.kind {
+                    Kind::Orphans => inlinks == 0,
+                    Kind::DeadEnds => outlinks == 0,
+                    Kind::Isolated => inlinks == 0 && outlinks == 0,
+                }
+            })
+            .filter(|n| {
+                let page = data.pages.get(*n);
+                self.redirect.is_none_or(|r| r == page.redirect)
+                    && self.min_length.is_none_or(|l| page.length >= l)
+                    && self.max_length.is_none_or(|l| page.length <= l)
+            })
+            .collect::<Vec<_>>();
+        let listed = &nodes[..nodes.len().min(self.limit.unwrap_or(usize::MAX))];
+
+        match format {
+            Format::Text => {
+                println!(
+                    "{} {}",
+                    nodes.len().separate_with_underscores(),
+                    self.kind.name()
+                );
+                println!();
+                for node in listed {
+                    let page = data.pages.get(*node);
+                    println!(
+                        "{} (length {})",
+                        util::fmt_page(&page),
+                        page.length.separate_with_underscores()
+                    );
+                }
+            }
+            Format::Json => {
+                let pages = listed
+                    .iter()
+                    .map(|n| page_json(data, *n))
+                    .collect::<Value>();
+                output::print_json(&json!({ "count": nodes.len(), "pages": pages }))?;
+            }
+            Format::Csv => {
+                let mut csv = Csv::new(&["title", "redirect", "length"])?;
+                for node in listed {
+                    let page = data.pages.get(*node);
+                    csv.row(&[&page.title, &page.redirect, &page.length])?;
+                }
+                csv.finish()?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn page_json(data: &Data, node: NodeIdx) -> Value {
+    let page = data.pages.get(node);
+    json!({ "title": page.title, "redirect": page.redirect, "length": page.length })
+}
@@ -1,19 +1,119 @@
 use std::{
     fs::File,
-    io::{self, BufReader, BufWriter, Read, Write},
+    io::{self, BufReader, BufWriter, Cursor, Read, Seek, SeekFrom, Write},
+    mem,
     path::Path,
+    str,
+    sync::Arc,
 };
 
-use crate::graph::{EdgeIdx, Graph, NodeIdx};
+use memmap2::Mmap;
 
-#[derive(Debug, Clone)]
-pub struct Page {
+use crate::{
+    error::{self, Error, Result},
+    graph::{EdgeIdx, Edges, Graph, NodeIdx, Packed, Reverse},
+    index::TitleIndex,
+    store::{Pod, Store},
+};
+
+/// Marks the start of a versioned brood datafile.
+///
+/// Files written before the header was introduced start directly with the page
+/// and link counts. They are treated as version 0.
+const MAGIC: [u8; 8] = *b"BROODDAT";
+
+/// The datafile version written by this version of brood.
+pub const VERSION: u32 = 4;
+
+/// Shown in place of titles that are not valid UTF-8.
+const INVALID_TITLE: &str = "\u{FFFD}";
+
+#[derive(Debug, Clone, Copy)]
+pub struct Page<'a> {
     pub id: u32,
-    pub title: String,
+    pub title: &'a str,
     pub length: u32,
     pub redirect: bool,
 }
 
+/// Metadata for every page, stored column by column like [`Links`].
+///
+/// All titles are stored back to back in a single buffer. Looking up a title
+/// only touches that title's bytes, so the titles of a memory-mapped datafile
+/// are effectively loaded on demand.
+#[derive(Default)]
+pub struct Pages {
+    pub ids: Store<u32>,
+    pub lengths: Store<u32>,
+    pub flags: Store<u8>,
+    /// For every page, the offset in `titles` where its title ends.
+    pub title_ends: Store<u32>,
+    pub titles: Store<u8>,
+}
+
+impl Pages {
+    const REDIRECT: u8 = 0b1;
+
+    pub fn with_capacity(pages: usize) -> Self {
+        Self {
+            ids: Store::with_capacity(pages),
+            lengths: Store::with_capacity(pages),
+            flags: Store::with_capacity(pages),
+            title_ends: Store::with_capacity(pages),
+            titles: Store::default(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn title(&self, node: NodeIdx) -> &str {
+        let i = node.usize();
+        let start = if i == 0 { 0 } else { self.title_ends[i - 1] };
+        let end = self.title_ends[i];
+        str::from_utf8(&self.titles[start as usize..end as usize]).unwrap_or(INVALID_TITLE)
+    }
+
+    pub fn redirect(&self, node: NodeIdx) -> bool {
+        self.flags[node.usize()] & Self::REDIRECT != 0
+    }
+
+    pub fn get(&self, node: NodeIdx) -> Page<'_> {
+        Page {
+            id: self.ids[node.usize()],
+            title: self.title(node),
+            length: self.lengths[node.usize()],
+            redirect: self.redirect(node),
+        }
+    }
+
+    pub fn push(&mut self, page: Page<'_>) {
+        let titles = self.titles.to_mut();
+        titles.extend_from_slice(page.title.as_bytes());
+        let title_end = u32::try_from(titles.len()).expect("too many title bytes");
+
+        self.ids.push(page.id);
+        self.lengths.push(page.length);
+        self.flags
+            .push(if page.redirect { Self::REDIRECT } else { 0 });
+        self.title_ends.push(title_end);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Page<'_>> + '_ {
+        (0..self.len()).map(|i| self.get(NodeIdx::new(i)))
+    }
+
+    /// Collect the given pages, in order, into a new owned table.
+    pub fn select(&self, nodes: &[NodeIdx]) -> Self {
+        let mut result = Self::with_capacity(nodes.len());
+        for node in nodes {
+            result.push(self.get(*node));
+        }
+        result
+    }
+}
+
 #[derive(Debug, Default, Clone, Copy)]
 pub struct Link {
     pub start: u32,
@@ -31,8 +131,87 @@ impl Link {
     }
 }
 
-fn write_u8(w: &mut impl Write, n: u8) -> io::Result<()> {
-    w.write_all(&n.to_le_bytes())
+/// Metadata for every edge of the graph, stored column by column.
+///
+/// Each column is a flat array of fixed-size values so that it can be borrowed
+/// straight from a memory-mapped datafile. Columns that were not loaded (see
+/// [`Load`]) are empty.
+#[derive(Default)]
+pub struct Links {
+    pub starts: Store<u32>,
+    pub lens: Store<u32>,
+    pub flags: Store<u8>,
+}
+
+impl Links {
+    pub fn with_capacity(links: usize) -> Self {
+        Self {
+            starts: Store::with_capacity(links),
+            lens: Store::with_capacity(links),
+            flags: Store::with_capacity(links),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.flags.len()
+    }
+
+    /// Requires at least [`Load::Flags`]. Unless the links were loaded with
+    /// [`Load::Full`], the start and length are always 0.
+    pub fn get(&self, edge: EdgeIdx) -> Link {
+        let i = edge.usize();
+        Link {
+            start: self.starts.get(i).copied().unwrap_or_default(),
+            len: self.lens.get(i).copied().unwrap_or_default(),
+            flags: self.flags[i],
+        }
+    }
+
+    pub fn push(&mut self, link: Link) {
+        self.starts.push(link.start);
+        self.lens.push(link.len);
+        self.flags.push(link.flags);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Link> + '_ {
+        (0..self.len()).map(|i| self.get(EdgeIdx::new(i)))
+    }
+
+    /// Collect the given links, in order, into a new owned table.
+    pub fn select(&self, edges: &[EdgeIdx]) -> Self {
+        let indices = || edges.iter().map(|e| e.usize());
+        Self {
+            starts: self.starts.select(indices()),
+            lens: self.lens.select(indices()),
+            flags: self.flags.select(indices()),
+        }
+    }
+
+    /// Drop all columns not needed for the given load level.
+    fn restrict(&mut self, load: Load) {
+        if load < Load::Full {
+            self.starts = Store::default();
+            self.lens = Store::default();
+        }
+        if load < Load::Flags {
+            self.flags = Store::default();
+        }
+    }
+}
+
+/// How much of a datafile to load.
+///
+/// Pages and the graph are always available. Commands that don't need all link
+/// metadata can use a lower level to save memory.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Load {
+    /// Only pages and graph.
+    Graph,
+    /// Additionally, the flags of all links.
+    Flags,
+    /// Everything, including the position of all links.
+    #[default]
+    Full,
 }
 
 fn read_u8(r: &mut impl Read) -> io::Result<u8> {
@@ -41,10 +220,6 @@ fn read_u8(r: &mut impl Read) -> io::Result<u8> {
     Ok(u8::from_le_bytes(buf))
 }
 
-fn write_u16(w: &mut impl Write, n: u16) -> io::Result<()> {
-    w.write_all(&n.to_le_bytes())
-}
-
 fn read_u16(r: &mut impl Read) -> io::Result<u16> {
     let mut buf = [0_u8; 2];
     r.read_exact(&mut buf)?;
@@ -61,44 +236,46 @@ fn read_u32(r: &mut impl Read) -> io::Result<u32> {
     Ok(u32::from_le_bytes(buf))
 }
 
-fn write_str(w: &mut impl Write, s: &str) -> io::Result<()> {
-    assert!(s.len() <= u16::MAX as usize);
-    write_u16(w, s.len() as u16)?;
-    w.write_all(s.as_bytes())?;
-    Ok(())
+fn write_u64(w: &mut impl Write, n: u64) -> io::Result<()> {
+    w.write_all(&n.to_le_bytes())
 }
 
-fn read_str(r: &mut impl Read) -> io::Result<String> {
-    let len = read_u16(r)? as usize;
-    let mut buf = vec![0_u8; len];
+fn read_u64(r: &mut impl Read) -> io::Result<u64> {
+    let mut buf = [0_u8; 8];
     r.read_exact(&mut buf)?;
-    Ok(String::from_utf8(buf).unwrap())
+    Ok(u64::from_le_bytes(buf))
 }
 
-fn write_page(w: &mut impl Write, page: &Page) -> io::Result<()> {
-    write_u32(w, page.id)?;
-    write_u32(w, page.length)?;
-    write_u8(w, if page.redirect { 1 } else { 0 })?;
-    write_str(w, &page.title)?;
+fn write_u32s(w: &mut impl Write, ns: &[u32]) -> io::Result<()> {
+    for n in ns {
+        write_u32(w, *n)?;
+    }
     Ok(())
 }
 
-pub fn read_page(r: &mut impl Read) -> io::Result<Page> {
-    Ok(Page {
-        id: read_u32(r)?,
-        length: read_u32(r)?,
-        redirect: read_u8(r)? != 0,
-        title: read_str(r)?,
-    })
+fn read_str(r: &mut impl Read) -> io::Result<String> {
+    let len = read_u16(r)? as usize;
+    let mut buf = vec![0_u8; len];
+    r.read_exact(&mut buf)?;
+    String::from_utf8(buf).map_err(|_| invalid_data("string is not valid UTF-8"))
 }
 
-fn write_link(w: &mut impl Write, link: &Link) -> io::Result<()> {
-    write_u32(w, link.start)?;
-    write_u32(w, link.len)?;
-    write_u8(w, link.flags)?;
+/// Read a page in the packed encoding used up to version 2.
+fn read_page(r: &mut impl Read, pages: &mut Pages) -> io::Result<()> {
+    let id = read_u32(r)?;
+    let length = read_u32(r)?;
+    let redirect = read_u8(r)? != 0;
+    let title = read_str(r)?;
+    pages.push(Page {
+        id,
+        title: &title,
+        length,
+        redirect,
+    });
     Ok(())
 }
 
+/// Read a link in the packed encoding used up to version 1.
 fn read_link(r: &mut impl Read) -> io::Result<Link> {
     Ok(Link {
         start: read_u32(r)?,
@@ -107,11 +284,515 @@ fn read_link(r: &mut impl Read) -> io::Result<Link> {
     })
 }
 
+fn invalid_data(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.into())
+}
+
+/// Turn an error encountered while parsing part of a datafile into a
+/// [`Error::Datafile`], unless reading the file itself failed.
+fn parse_error(offset: Option<u64>, err: io::Error) -> Error {
+    let msg = match err.kind() {
+        io::ErrorKind::InvalidData => err.to_string(),
+        io::ErrorKind::UnexpectedEof => "unexpected end of data".to_string(),
+        _ => return Error::Io(err),
+    };
+
+    Error::Datafile { offset, msg }
+}
+
+/// Feeds all bytes passing through a reader or writer into a checksum.
+struct Checksummed<T> {
+    inner: T,
+    hasher: crc32fast::Hasher,
+}
+
+impl<T> Checksummed<T> {
+    fn new(inner: T) -> Self {
+        Self {
+            inner,
+            hasher: crc32fast::Hasher::new(),
+        }
+    }
+}
+
+impl<R: Read> Read for Checksummed<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+}
+
+impl<W: Write> Write for Checksummed<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SectionKind {
+    /// Pages as packed records, only used up to version 2.
+    Pages,
+    /// Links as packed records, only used by version 1.
+    Links,
+    Nodes,
+    Edges,
+    LinkStarts,
+    LinkLens,
+    LinkFlags,
+    PageIds,
+    PageLengths,
+    PageFlags,
+    TitleEnds,
+    Titles,
+    PackedOffsets,
+    PackedEdges,
+    TitleIndex,
+    TitleKeyEnds,
+    TitleKeys,
+    ReverseNodes,
+    ReverseSources,
+    ReverseEdges,
+    Fingerprint,
+}
+
+impl SectionKind {
+    const ALL: [Self; 21] = [
+        Self::Pages,
+        Self::Links,
+        Self::Nodes,
+        Self::Edges,
+        Self::LinkStarts,
+        Self::LinkLens,
+        Self::LinkFlags,
+        Self::PageIds,
+        Self::PageLengths,
+        Self::PageFlags,
+        Self::TitleEnds,
+        Self::Titles,
+        Self::PackedOffsets,
+        Self::PackedEdges,
+        Self::TitleIndex,
+        Self::TitleKeyEnds,
+        Self::TitleKeys,
+        Self::ReverseNodes,
+        Self::ReverseSources,
+        Self::ReverseEdges,
+        Self::Fingerprint,
+    ];
+
+    fn id(self) -> u32 {
+        match self {
+            Self::Pages => 1,
+            Self::Links => 2,
+            Self::Nodes => 3,
+            Self::Edges => 4,
+            Self::LinkStarts => 5,
+            Self::LinkLens => 6,
+            Self::LinkFlags => 7,
+            Self::PageIds => 8,
+            Self::PageLengths => 9,
+            Self::PageFlags => 10,
+            Self::TitleEnds => 11,
+            Self::Titles => 12,
+            Self::PackedOffsets => 13,
+            Self::PackedEdges => 14,
+            Self::TitleIndex => 15,
+            Self::TitleKeyEnds => 16,
+            Self::TitleKeys => 17,
+            Self::ReverseNodes => 18,
+            Self::ReverseSources => 19,
+            Self::ReverseEdges => 20,
+            Self::Fingerprint => 21,
+        }
+    }
+
+    fn from_id(id: u32) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.id() == id)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Pages => "pages",
+            Self::Links => "links",
+            Self::Nodes => "nodes",
+            Self::Edges => "edges",
+            Self::LinkStarts => "lstarts",
+            Self::LinkLens => "llens",
+            Self::LinkFlags => "lflags",
+            Self::PageIds => "pids",
+            Self::PageLengths => "plengths",
+            Self::PageFlags => "pflags",
+            Self::TitleEnds => "tends",
+            Self::Titles => "titles",
+            Self::PackedOffsets => "poffsets",
+            Self::PackedEdges => "pedges",
+            Self::TitleIndex => "tindex",
+            Self::TitleKeyEnds => "tkends",
+            Self::TitleKeys => "tkeys",
+            Self::ReverseNodes => "rnodes",
+            Self::ReverseSources => "rsources",
+            Self::ReverseEdges => "redges",
+            Self::Fingerprint => "fprint",
+        }
+    }
+}
+
+/// An entry in the section table of a datafile header.
+#[derive(Debug, Clone, Copy)]
+pub struct Section {
+    /// Raw section kind, see [`SectionKind`].
+    pub id: u32,
+    /// Offset of the section's first byte from the start of the file.
+    pub offset: u64,
+    pub len: u64,
+    /// CRC-32 of the section's bytes.
+    pub checksum: u32,
+}
+
+impl Section {
+    const PLACEHOLDER: Self = Self {
+        id: 0,
+        offset: 0,
+        len: 0,
+        checksum: 0,
+    };
+
+    pub fn kind(&self) -> Option<SectionKind> {
+        SectionKind::from_id(self.id)
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.kind().map(|k| k.name()).unwrap_or("unknown")
+    }
+
+    fn write_entry(&self, w: &mut impl Write) -> io::Result<()> {
+        write_u32(w, self.id)?;
+        write_u32(w, self.checksum)?;
+        write_u64(w, self.offset)?;
+        write_u64(w, self.len)?;
+        Ok(())
+    }
+
+    fn read_entry(r: &mut impl Read) -> io::Result<Self> {
+        Ok(Self {
+            id: read_u32(r)?,
+            checksum: read_u32(r)?,
+            offset: read_u64(r)?,
+            len: read_u64(r)?,
+        })
+    }
+
+    /// Read the section's contents, then verify its length and checksum.
+    fn read_contents<R, T>(
+        &self,
+        r: &mut R,
+        f: impl FnOnce(&mut Checksummed<io::Take<&mut R>>) -> io::Result<T>,
+    ) -> Result<T>
+    where
+        R: Read + Seek,
+    {
+        r.seek(SeekFrom::Start(self.offset))?;
+        let mut section = Checksummed::new(r.by_ref().take(self.len));
+
+        let result = f(&mut section);
+
+        // When the contents are malformed, a truncated file or a checksum
+        // mismatch is the more useful explanation, so we read the rest of the
+        // section to find out.
+        if result.is_err() {
+            io::copy(&mut section, &mut io::sink())?;
+        }
+
+        let truncated = section.inner.limit() != 0;
+        let checksum_ok = section.hasher.finalize() == self.checksum;
+        let result = match result {
+            Ok(result) => result,
+            Err(_) if truncated => {
+                return Err(Error::datafile_at(
+                    self.offset,
+                    format!("{} section is truncated", self.name()),
+                ))
+            }
+            Err(_) if !checksum_ok => {
+                return Err(Error::datafile_at(
+                    self.offset,
+                    format!("{} section has an invalid checksum", self.name()),
+                ))
+            }
+            Err(err) => {
+                return Err(match parse_error(Some(self.offset), err) {
+                    Error::Datafile { offset, msg } => Error::Datafile {
+                        offset,
+                        msg: format!("{} section: {msg}", self.name()),
+                    },
+                    err => err,
+                })
+            }
+        };
+
+        if section.inner.limit() != 0 {
+            return Err(Error::datafile_at(
+                self.offset,
+                format!("{} section has trailing bytes", self.name()),
+            ));
+        }
+
+        if !checksum_ok {
+            return Err(Error::datafile_at(
+                self.offset,
+                format!("{} section has an invalid checksum", self.name()),
+            ));
+        }
+
+        Ok(result)
+    }
+
+    /// Borrow the section's contents as an array of `len` values.
+    fn map<T: Pod>(&self, map: &Arc<Mmap>, len: usize) -> Result<Store<T>> {
+        if self.len != (len * mem::size_of::<T>()) as u64 {
+            return Err(Error::datafile_at(
+                self.offset,
+                format!("{} section has the wrong size", self.name()),
+            ));
+        }
+
+        Store::map(map, self.offset, len).ok_or_else(|| {
+            Error::datafile_at(
+                self.offset,
+                format!("{} section is truncated or misaligned", self.name()),
+            )
+        })
+    }
+
+    /// Check the checksum of the section's contents in a memory map.
+    fn verify_mapped(&self, map: &Mmap) -> Result<()> {
+        let bytes = usize::try_from(self.offset)
+            .ok()
+            .zip(usize::try_from(self.len).ok())
+            .and_then(|(offset, len)| map.get(offset..offset.checked_add(len)?))
+            .ok_or_else(|| {
+                Error::datafile_at(self.offset, format!("{} section is truncated", self.name()))
+            })?;
+
+        if crc32fast::hash(bytes) != self.checksum {
+            return Err(Error::datafile_at(
+                self.offset,
+                format!("{} section has an invalid checksum", self.name()),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Check the section's checksum without interpreting its contents.
+    pub fn verify<R: Read + Seek>(&self, r: &mut R) -> Result<()> {
+        self.read_contents(r, |r| {
+            let len = io::copy(r, &mut io::sink())?;
+            if len < self.len {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Sections whose checksums are verified when a datafile is memory-mapped.
+///
+/// These have at most one entry per page, so they are cheap to verify compared
+/// to the titles and the sections with one entry per link.
+const VERIFIED_ON_MAP: [SectionKind; 10] = [
+    SectionKind::PageIds,
+    SectionKind::PageLengths,
+    SectionKind::PageFlags,
+    SectionKind::TitleEnds,
+    SectionKind::Nodes,
+    SectionKind::PackedOffsets,
+    SectionKind::ReverseNodes,
+    SectionKind::TitleIndex,
+    SectionKind::TitleKeyEnds,
+    SectionKind::Fingerprint,
+];
+
+/// Sections start at multiples of this many bytes so that their contents can
+/// be borrowed from a memory map.
+const SECTION_ALIGN: u64 = 8;
+
+fn write_section<W: Write + Seek>(
+    w: &mut W,
+    kind: SectionKind,
+    f: impl FnOnce(&mut Checksummed<&mut W>) -> io::Result<()>,
+) -> io::Result<Section> {
+    let pos = w.stream_position()?;
+    let padding = pos.next_multiple_of(SECTION_ALIGN) - pos;
+    w.write_all(&[0; SECTION_ALIGN as usize][..padding as usize])?;
+
+    let offset = w.stream_position()?;
+    let mut section = Checksummed::new(&mut *w);
+    f(&mut section)?;
+    let checksum = section.hasher.finalize();
+    let len = w.stream_position()? - offset;
+
+    Ok(Section {
+        id: kind.id(),
+        offset,
+        len,
+        checksum,
+    })
+}
+
+/// Datafile header.
+///
+/// Starting with version 1, the header consists of the magic bytes, the
+/// version, the page and link counts, and a table of sections. Version 0 files
+/// only have the counts.
+///
+/// Version 2 aligns all sections and stores the graph and link metadata as
+/// arrays of fixed-size values, which allows them to be memory-mapped. Version
+/// 3 does the same for the page metadata and titles. Version 4 may store the
+/// edges as packed adjacency lists instead, see [`Packed`].
+///
+/// Files of version 2 and newer may additionally contain a [`TitleIndex`], the
+/// in-edges of the graph (see [`Reverse`]) and its [`Data::fingerprint`].
+#[derive(Debug, Clone)]
+pub struct Header {
+    pub version: u32,
+    pub pages: u32,
+    pub links: u32,
+    pub sections: Vec<Section>,
+}
+
+impl Header {
+    fn write(&self, w: &mut impl Write) -> io::Result<()> {
+        w.write_all(&MAGIC)?;
+        write_u32(w, self.version)?;
+        write_u32(w, self.pages)?;
+        write_u32(w, self.links)?;
+        write_u32(w, self.sections.len() as u32)?;
+        for section in &self.sections {
+            section.write_entry(w)?;
+        }
+        Ok(())
+    }
+
+    fn read<R: Read + Seek>(r: &mut R) -> Result<Self> {
+        Self::read_fields(r).map_err(|err| match err {
+            Error::Io(err) => parse_error(Some(0), err),
+            err => err,
+        })
+    }
+
+    fn read_fields<R: Read + Seek>(r: &mut R) -> Result<Self> {
+        let mut magic = [0_u8; MAGIC.len()];
+        let has_magic = match r.read_exact(&mut magic) {
+            Ok(()) => magic == MAGIC,
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => false,
+            Err(err) => return Err(err.into()),
+        };
+
+        if !has_magic {
+            r.seek(SeekFrom::Start(0))?;
+            let result = Self {
+                version: 0,
+                pages: read_u32(r)?,
+                links: read_u32(r)?,
+                sections: vec![],
+            };
+            result.check_counts(r)?;
+            return Ok(result);
+        }
+
+        let version = read_u32(r)?;
+        if version == 0 || version > VERSION {
+            return Err(Error::datafile_at(
+                MAGIC.len() as u64,
+                format!("unsupported datafile version {version} (this brood supports versions up to {VERSION})"),
+            ));
+        }
+
+        let pages = read_u32(r)?;
+        let links = read_u32(r)?;
+
+        let n_sections = read_u32(r)?;
+        let mut sections = vec![];
+        for _ in 0..n_sections {
+            sections.push(Section::read_entry(r)?);
+        }
+
+        let result = Self {
+            version,
+            pages,
+            links,
+            sections,
+        };
+        result.check_counts(r)?;
+        Ok(result)
+    }
+
+    /// Check that the page and link counts fit into the rest of the file,
+    /// before they are used to allocate memory.
+    ///
+    /// Up to version 1, every page takes at least 15 bytes (its fields, an
+    /// empty title and its node) and every link 13 bytes (its fields and its
+    /// edge). Later versions still store a node per page and at least one byte
+    /// per packed edge.
+    fn check_counts<R: Read + Seek>(&self, r: &mut R) -> Result<()> {
+        let pos = r.stream_position()?;
+        let file_len = r.seek(SeekFrom::End(0))?;
+        r.seek(SeekFrom::Start(pos))?;
+
+        let (page_size, link_size) = if self.version <= 1 { (15, 13) } else { (4, 1) };
+        let needed = u64::from(self.pages) * page_size + u64::from(self.links) * link_size;
+        if needed > file_len - pos {
+            return Err(Error::datafile(format!(
+                "{} pages and {} links don't fit into the {} bytes after the header",
+                self.pages,
+                self.links,
+                file_len - pos
+            )));
+        }
+        Ok(())
+    }
+
+    pub fn read_from_file(path: &Path) -> Result<Self> {
+        let mut file = BufReader::new(File::open(path)?);
+        Self::read(&mut file)
+    }
+
+    pub fn is_current(&self) -> bool {
+        self.version == VERSION
+    }
+
+    pub fn is_mappable(&self) -> bool {
+        self.version >= 2
+    }
+
+    pub fn find_section(&self, kind: SectionKind) -> Option<&Section> {
+        self.sections.iter().find(|s| s.kind() == Some(kind))
+    }
+
+    pub fn section(&self, kind: SectionKind) -> Result<&Section> {
+        self.find_section(kind)
+            .ok_or_else(|| Error::datafile(format!("missing {} section", kind.name())))
+    }
+}
+
 #[derive(Default)]
 pub struct Data {
-    pub pages: Vec<Page>,
-    pub links: Vec<Link>,
+    pub pages: Pages,
+    pub links: Links,
     pub graph: Graph,
+    pub load: Load,
+    /// Must be dropped when pages are added, removed or reordered.
+    pub title_index: Option<TitleIndex>,
+    /// The [`Self::fingerprint`] stored in the datafile. Must be dropped when
+    /// the graph or the redirect flags change.
+    pub graph_fingerprint: Option<u32>,
 }
 
 impl Data {
@@ -121,98 +802,510 @@ impl Data {
 
     pub fn with_capacity(pages: usize, links: usize) -> Self {
         Self {
-            pages: Vec::with_capacity(pages),
-            links: Vec::with_capacity(links),
+            pages: Pages::with_capacity(pages),
+            links: Links::with_capacity(links),
             graph: Graph::with_capacity(pages, links),
+            load: Load::Full,
+            title_index: None,
+            graph_fingerprint: None,
         }
     }
 
-    fn write(&self, w: &mut impl Write) -> io::Result<()> {
+    /// A checksum of the graph and of which pages are redirects, everything
+    /// that distances between articles depend on.
+    ///
+    /// Only computed if it isn't stored in the datafile or the graph changed
+    /// since it was loaded.
+    pub fn fingerprint(&self) -> u32 {
+        if let Some(fingerprint) = self.graph_fingerprint {
+            return fingerprint;
+        }
+
+        let mut hasher = crc32fast::Hasher::new();
+        hasher.update(&(self.graph.nodes.len() as u32).to_le_bytes());
+        for node in self.graph.nodes() {
+            hasher.update(&[self.pages.redirect(node) as u8]);
+            hasher.update(&(self.graph.edge_range(node).len() as u32).to_le_bytes());
+            for target in self.graph.targets(node) {
+                hasher.update(&target.0.to_le_bytes());
+            }
+        }
+        hasher.finalize()
+    }
+
+    fn write<W: Write + Seek>(&self, w: &mut W) -> io::Result<()> {
+        assert_eq!(self.load, Load::Full, "data must be fully loaded");
         assert!(self.pages.len() < u32::MAX as usize);
         assert!(self.links.len() < u32::MAX as usize);
         assert_eq!(self.pages.len(), self.graph.nodes.len());
         assert_eq!(self.links.len(), self.graph.edges.len());
-        write_u32(w, self.pages.len() as u32)?;
-        write_u32(w, self.links.len() as u32)?;
 
-        for page in &self.pages {
-            write_page(w, page)?;
-        }
+        let n_sections = 11
+            + self.graph.is_packed() as usize
+            + 3 * self.title_index.is_some() as usize
+            + 3 * self.graph.reverse.is_some() as usize;
+        let mut header = Header {
+            version: VERSION,
+            pages: self.pages.len() as u32,
+            links: self.links.len() as u32,
+            sections: vec![Section::PLACEHOLDER; n_sections],
+        };
+
+        // The header has a fixed size, so it can be overwritten once the
+        // section offsets and checksums are known.
+        header.write(w)?;
+
+        let mut sections = vec![];
+
+        sections.push(write_section(w, SectionKind::PageIds, |w| {
+            write_u32s(w, &self.pages.ids)
+        })?);
+
+        sections.push(write_section(w, SectionKind::PageLengths, |w| {
+            write_u32s(w, &self.pages.lengths)
+        })?);
+
+        sections.push(write_section(w, SectionKind::PageFlags, |w| {
+            w.write_all(&self.pages.flags)
+        })?);
+
+        sections.push(write_section(w, SectionKind::TitleEnds, |w| {
+            write_u32s(w, &self.pages.title_ends)
+        })?);
 
-        for link in &self.links {
-            write_link(w, link)?;
+        sections.push(write_section(w, SectionKind::Titles, |w| {
+            w.write_all(&self.pages.titles)
+        })?);
+
+        sections.push(write_section(w, SectionKind::Nodes, |w| {
+            for node in &self.graph.nodes {
+                write_u32(w, node.0)?;
+            }
+            Ok(())
+        })?);
+
+        match &self.graph.edges {
+            Edges::Plain(targets) => {
+                sections.push(write_section(w, SectionKind::Edges, |w| {
+                    for target in targets {
+                        write_u32(w, target.0)?;
+                    }
+                    Ok(())
+                })?);
+            }
+            Edges::Packed(packed) => {
+                sections.push(write_section(w, SectionKind::PackedOffsets, |w| {
+                    for offset in &packed.offsets {
+                        write_u64(w, *offset)?;
+                    }
+                    Ok(())
+                })?);
+
+                sections.push(write_section(w, SectionKind::PackedEdges, |w| {
+                    w.write_all(&packed.bytes)
+                })?);
+            }
         }
 
-        for node in &self.graph.nodes {
-            write_u32(w, node.0)?;
+        sections.push(write_section(w, SectionKind::Fingerprint, |w| {
+            write_u32(w, self.fingerprint())
+        })?);
+
+        sections.push(write_section(w, SectionKind::LinkStarts, |w| {
+            write_u32s(w, &self.links.starts)
+        })?);
+
+        sections.push(write_section(w, SectionKind::LinkLens, |w| {
+            write_u32s(w, &self.links.lens)
+        })?);
+
+        sections.push(write_section(w, SectionKind::LinkFlags, |w| {
+            w.write_all(&self.links.flags)
+        })?);
+
+        if let Some(reverse) = &self.graph.reverse {
+            sections.push(write_section(w, SectionKind::ReverseNodes, |w| {
+                for node in &reverse.nodes {
+                    write_u32(w, node.0)?;
+                }
+                Ok(())
+            })?);
+
+            sections.push(write_section(w, SectionKind::ReverseSources, |w| {
+                for source in &reverse.sources {
+                    write_u32(w, source.0)?;
+                }
+                Ok(())
+            })?);
+
+            sections.push(write_section(w, SectionKind::ReverseEdges, |w| {
+                for edge in &reverse.edges {
+                    write_u32(w, edge.0)?;
+                }
+                Ok(())
+            })?);
         }
 
-        for edge in &self.graph.edges {
-            write_u32(w, edge.0)?;
+        if let Some(index) = &self.title_index {
+            sections.push(write_section(w, SectionKind::TitleIndex, |w| {
+                for node in &index.nodes {
+                    write_u32(w, node.0)?;
+                }
+                Ok(())
+            })?);
+
+            sections.push(write_section(w, SectionKind::TitleKeyEnds, |w| {
+                write_u32s(w, &index.key_ends)
+            })?);
+
+            sections.push(write_section(w, SectionKind::TitleKeys, |w| {
+                w.write_all(&index.keys)
+            })?);
         }
 
+        assert_eq!(sections.len(), header.sections.len());
+        header.sections = sections;
+
+        w.seek(SeekFrom::Start(0))?;
+        header.write(w)?;
         Ok(())
     }
 
-    fn read(r: &mut impl Read) -> io::Result<Self> {
-        let n_pages = read_u32(r)?;
-        let n_links = read_u32(r)?;
-
-        let mut result = Self::with_capacity(n_pages as usize, n_links as usize);
+    /// Read the layout from before datafiles had a header.
+    ///
+    /// It contains the same sections as version 1 in the same encoding, just
+    /// without a section table or checksums.
+    fn read_v0(r: &mut impl Read, header: &Header) -> io::Result<Self> {
+        let mut result = Self::with_capacity(header.pages as usize, header.links as usize);
 
-        for _ in 0..n_pages {
-            result.pages.push(read_page(r)?);
+        for _ in 0..header.pages {
+            read_page(r, &mut result.pages)?;
         }
 
-        for _ in 0..n_links {
+        for _ in 0..header.links {
             result.links.push(read_link(r)?);
         }
 
-        for _ in 0..n_pages {
+        for _ in 0..header.pages {
             result.graph.nodes.push(EdgeIdx(read_u32(r)?));
         }
 
-        for _ in 0..n_links {
-            result.graph.edges.push(NodeIdx(read_u32(r)?));
+        for _ in 0..header.links {
+            result.graph.add_edge(NodeIdx(read_u32(r)?));
+        }
+
+        Ok(result)
+    }
+
+    fn read_v1<R: Read + Seek>(r: &mut R, header: &Header) -> Result<Self> {
+        let mut result = Self::with_capacity(header.pages as usize, header.links as usize);
+
+        header.section(SectionKind::Pages)?.read_contents(r, |r| {
+            for _ in 0..header.pages {
+                read_page(r, &mut result.pages)?;
+            }
+            Ok(())
+        })?;
+
+        header.section(SectionKind::Links)?.read_contents(r, |r| {
+            for _ in 0..header.links {
+                result.links.push(read_link(r)?);
+            }
+            Ok(())
+        })?;
+
+        header.section(SectionKind::Nodes)?.read_contents(r, |r| {
+            for _ in 0..header.pages {
+                result.graph.nodes.push(EdgeIdx(read_u32(r)?));
+            }
+            Ok(())
+        })?;
+
+        header.section(SectionKind::Edges)?.read_contents(r, |r| {
+            for _ in 0..header.links {
+                result.graph.add_edge(NodeIdx(read_u32(r)?));
+            }
+            Ok(())
+        })?;
+
+        Ok(result)
+    }
+
+    /// Map the sections of a version 2 or newer file into memory.
+    ///
+    /// Only the sections required by the load level are mapped. Version 2
+    /// files still store their pages in packed form, so those are read.
+    ///
+    /// To keep loading fast, only the checksums of the small sections listed in
+    /// [`VERIFIED_ON_MAP`] are verified, and only if `verify` is set. The graph
+    /// is only checked for indices out of bounds (see [`Self::check_bounds`]).
+    /// Use the `info --verify` and `--check-consistency` options for more.
+    fn map(file: &File, header: &Header, load: Load, verify: bool) -> Result<Self> {
+        // SAFETY: The datafile must not be modified while brood is running.
+        let map = Arc::new(unsafe { Mmap::map(file)? });
+
+        if verify {
+            for kind in VERIFIED_ON_MAP {
+                if let Some(section) = header.find_section(kind) {
+                    section.verify_mapped(&map)?;
+                }
+            }
+        }
+
+        let n_pages = header.pages as usize;
+        let n_links = header.links as usize;
+
+        let pages = if header.version == 2 {
+            let mut pages = Pages::with_capacity(n_pages);
+            header
+                .section(SectionKind::Pages)?
+                .read_contents(&mut Cursor::new(&map[..]), |r| {
+                    for _ in 0..n_pages {
+                        read_page(r, &mut pages)?;
+                    }
+                    Ok(())
+                })?;
+            pages
+        } else {
+            let titles = header.section(SectionKind::Titles)?;
+            Pages {
+                ids: header.section(SectionKind::PageIds)?.map(&map, n_pages)?,
+                lengths: header
+                    .section(SectionKind::PageLengths)?
+                    .map(&map, n_pages)?,
+                flags: header.section(SectionKind::PageFlags)?.map(&map, n_pages)?,
+                title_ends: header.section(SectionKind::TitleEnds)?.map(&map, n_pages)?,
+                titles: titles.map(&map, titles.len as usize)?,
+            }
+        };
+
+        let edges = match header.find_section(SectionKind::PackedEdges) {
+            Some(bytes) => Edges::Packed(Packed {
+                offsets: header
+                    .section(SectionKind::PackedOffsets)?
+                    .map(&map, n_pages)?,
+                bytes: bytes.map(&map, bytes.len as usize)?,
+                len: n_links,
+            }),
+            None => Edges::Plain(header.section(SectionKind::Edges)?.map(&map, n_links)?),
+        };
+
+        let reverse = match header.find_section(SectionKind::ReverseNodes) {
+            Some(nodes) => Some(Reverse {
+                nodes: nodes.map(&map, n_pages)?,
+                sources: header
+                    .section(SectionKind::ReverseSources)?
+                    .map(&map, n_links)?,
+                edges: header
+                    .section(SectionKind::ReverseEdges)?
+                    .map(&map, n_links)?,
+            }),
+            None => None,
+        };
+
+        let graph = Graph {
+            nodes: header.section(SectionKind::Nodes)?.map(&map, n_pages)?,
+            edges,
+            reverse,
+        };
+
+        let mut links = Links::default();
+        if load >= Load::Flags {
+            links.flags = header.section(SectionKind::LinkFlags)?.map(&map, n_links)?;
+        }
+        if load >= Load::Full {
+            links.starts = header
+                .section(SectionKind::LinkStarts)?
+                .map(&map, n_links)?;
+            links.lens = header.section(SectionKind::LinkLens)?.map(&map, n_links)?;
         }
 
-        assert_eq!(result.pages.len(), result.graph.nodes.len());
-        assert_eq!(result.links.len(), result.graph.edges.len());
-        result.graph.check_consistency();
+        let title_index = match header.find_section(SectionKind::TitleIndex) {
+            Some(nodes) => {
+                let keys = header.section(SectionKind::TitleKeys)?;
+                Some(TitleIndex {
+                    nodes: nodes.map(&map, n_pages)?,
+                    key_ends: header
+                        .section(SectionKind::TitleKeyEnds)?
+                        .map(&map, n_pages)?,
+                    keys: keys.map(&map, keys.len as usize)?,
+                })
+            }
+            None => None,
+        };
+
+        let graph_fingerprint = match header.find_section(SectionKind::Fingerprint) {
+            Some(section) => Some(section.map::<u32>(&map, 1)?[0]),
+            None => None,
+        };
+
+        let result = Self {
+            pages,
+            links,
+            graph,
+            load,
+            title_index,
+            graph_fingerprint,
+        };
+        result.check_bounds()?;
         Ok(result)
     }
 
     pub fn write_to_file(&self, path: &Path) -> io::Result<()> {
         let mut file = BufWriter::new(File::create(path)?);
-        self.write(&mut file)
+        self.write(&mut file)?;
+        file.flush()
     }
 
-    pub fn read_from_file(path: &Path) -> io::Result<Self> {
+    /// Read a datafile of any supported version.
+    ///
+    /// Version 2 files and newer are memory-mapped, see [`Self::map`] for what
+    /// `verify` does. Older versions are read completely, verified and checked
+    /// for consistency before dropping the link metadata not needed by the
+    /// load level.
+    pub fn read_from_file(path: &Path, load: Load, verify: bool) -> Result<Self> {
         let mut file = BufReader::new(File::open(path)?);
-        Self::read(&mut file)
+        let header = Header::read(&mut file)?;
+
+        if header.is_mappable() {
+            return Self::map(file.get_ref(), &header, load, verify);
+        }
+
+        let mut result = match header.version {
+            0 => Self::read_v0(&mut file, &header).map_err(|err| parse_error(None, err))?,
+            _ => Self::read_v1(&mut file, &header)?,
+        };
+
+        result.check_consistency()?;
+        result.links.restrict(load);
+        result.load = load;
+        Ok(result)
+    }
+
+    /// Check that every index stored in the data is in range, so that using it
+    /// can't panic.
+    pub fn check_bounds(&self) -> Result<()> {
+        self.check_page_data()?;
+        self.graph.check_bounds()
+    }
+
+    pub fn check_consistency(&self) -> Result<()> {
+        self.check_page_data()?;
+        self.graph.check_consistency()
     }
 
-    pub fn check_consistency(&self) {
-        assert_eq!(
-            self.pages.len(),
-            self.graph.nodes.len(),
-            "inconsistent number of pages"
-        );
+    fn check_page_data(&self) -> Result<()> {
+        let n_pages = self.pages.len();
+        error::check(
+            n_pages == self.graph.nodes.len(),
+            "inconsistent number of pages",
+        )?;
+
+        error::check(
+            self.pages.lengths.len() == n_pages
+                && self.pages.flags.len() == n_pages
+                && self.pages.title_ends.len() == n_pages,
+            "inconsistent page data",
+        )?;
 
-        assert_eq!(
-            self.links.len(),
-            self.graph.edges.len(),
-            "inconsistent number of links"
-        );
+        let mut prev_end = 0;
+        for end in &self.pages.title_ends {
+            error::check(*end >= prev_end, "title ends must be well-ordered")?;
+            prev_end = *end;
+        }
+        error::check(
+            prev_end as usize == self.pages.titles.len(),
+            "title ends must be in range",
+        )?;
 
-        self.graph.check_consistency();
+        let n_links = self.graph.edges.len();
+        if self.load >= Load::Flags {
+            error::check(
+                self.links.flags.len() == n_links,
+                "inconsistent number of links",
+            )?;
+        }
+        if self.load >= Load::Full {
+            error::check(
+                self.links.starts.len() == n_links && self.links.lens.len() == n_links,
+                "inconsistent number of links",
+            )?;
+        }
+
+        if let Some(index) = &self.title_index {
+            error::check(
+                index.len() == n_pages && index.key_ends.len() == n_pages,
+                "inconsistent title index",
+            )?;
+            for node in &index.nodes {
+                error::check(node.usize() < n_pages, "title index must be in range")?;
+            }
+
+            let mut prev_end = 0;
+            for end in &index.key_ends {
+                error::check(*end >= prev_end, "title key ends must be well-ordered")?;
+                prev_end = *end;
+            }
+            error::check(
+                prev_end as usize == index.keys.len(),
+                "title key ends must be in range",
+            )?;
+        }
+
+        Ok(())
     }
 
     pub fn redirect_target(&self, node: NodeIdx) -> Option<NodeIdx> {
-        if !self.pages[node.usize()].redirect {
+        if !self.pages.redirect(node) {
             return None;
         }
 
-        self.graph.edge_slice(node).first().copied()
+        self.graph.targets(node).next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header_bytes(version: Option<u32>, pages: u32, links: u32) -> Vec<u8> {
+        let mut bytes = vec![];
+        match version {
+            Some(version) => {
+                let header = Header {
+                    version,
+                    pages,
+                    links,
+                    sections: vec![],
+                };
+                header.write(&mut bytes).unwrap();
+            }
+            None => {
+                write_u32(&mut bytes, pages).unwrap();
+                write_u32(&mut bytes, links).unwrap();
+            }
+        }
+        bytes
+    }
+
+    #[test]
+    fn counts_must_fit_into_file() {
+        let read = |bytes: Vec<u8>| Header::read(&mut Cursor::new(bytes));
+
+        for version in [None, Some(1), Some(VERSION)] {
+            let result = read(header_bytes(version, u32::MAX, u32::MAX));
+            assert!(matches!(result, Err(Error::Datafile { .. })));
+        }
+
+        // One page and link of version 1 need 28 bytes after the header.
+        let mut truncated = header_bytes(Some(1), 1, 1);
+        truncated.resize(truncated.len() + 27, 0);
+        assert!(matches!(
+            read(truncated.clone()),
+            Err(Error::Datafile { .. })
+        ));
+        truncated.push(0);
+        let header = read(truncated).unwrap();
+        assert_eq!((header.pages, header.links), (1, 1));
     }
 }
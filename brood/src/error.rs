@@ -0,0 +1,110 @@
+use std::{fmt, io, process::ExitCode};
+
+/// Everything that can go wrong while running brood.
+///
+/// Each kind of error exits with its own code so that scripts wrapping brood
+/// can tell them apart. Exit code 2 is used by clap for invalid arguments.
+#[derive(Debug)]
+pub enum Error {
+    /// Reading or writing a file failed. Exits with code 1.
+    Io(io::Error),
+    /// No article with the given title exists. Exits with code 3.
+    ArticleNotFound {
+        title: String,
+        /// Titles of similar articles, best match first.
+        suggestions: Vec<String>,
+    },
+    /// The datafile is malformed, truncated or inconsistent. Exits with code 4.
+    Datafile { offset: Option<u64>, msg: String },
+    /// A line of the sift data could not be parsed. Exits with code 5.
+    Sift { line: usize, msg: String },
+    /// A line of a file given as input to a command could not be parsed.
+    /// Exits with code 6.
+    Input { line: usize, msg: String },
+    /// A landmarks file is malformed or doesn't belong to the graph. Exits
+    /// with code 7.
+    Landmarks(String),
+    /// A search can't answer the query for this graph. Exits with code 8.
+    Search(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    pub fn datafile(msg: impl Into<String>) -> Self {
+        Self::Datafile {
+            offset: None,
+            msg: msg.into(),
+        }
+    }
+
+    pub fn datafile_at(offset: u64, msg: impl Into<String>) -> Self {
+        Self::Datafile {
+            offset: Some(offset),
+            msg: msg.into(),
+        }
+    }
+
+    pub fn exit_code(&self) -> ExitCode {
+        match self {
+            Self::Io(_) => ExitCode::from(1),
+            Self::ArticleNotFound { .. } => ExitCode::from(3),
+            Self::Datafile { .. } => ExitCode::from(4),
+            Self::Sift { .. } => ExitCode::from(5),
+            Self::Input { .. } => ExitCode::from(6),
+            Self::Landmarks(_) => ExitCode::from(7),
+            Self::Search(_) => ExitCode::from(8),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "{err}"),
+            Self::ArticleNotFound { title, suggestions } => {
+                write!(f, "article not found: {title:?}")?;
+                if !suggestions.is_empty() {
+                    write!(f, "\nDid you mean:")?;
+                    for suggestion in suggestions {
+                        write!(f, "\n  {suggestion}")?;
+                    }
+                }
+                Ok(())
+            }
+            Self::Datafile {
+                offset: Some(offset),
+                msg,
+            } => write!(f, "corrupt datafile at offset {offset}: {msg}"),
+            Self::Datafile { offset: None, msg } => write!(f, "corrupt datafile: {msg}"),
+            Self::Sift { line, msg } => write!(f, "invalid sift data on line {line}: {msg}"),
+            Self::Input { line, msg } => write!(f, "invalid input on line {line}: {msg}"),
+            Self::Landmarks(msg) => write!(f, "invalid landmarks: {msg}"),
+            Self::Search(msg) => write!(f, "search failed: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+/// Fail with a [`Error::Datafile`] unless the condition holds.
+pub fn check(cond: bool, msg: &str) -> Result<()> {
+    if cond {
+        Ok(())
+    } else {
+        Err(Error::datafile(msg))
+    }
+}
@@ -1,6 +1,16 @@
-use std::ops::{Add, AddAssign, Range, Sub, SubAssign};
+use std::{
+    iter, mem,
+    ops::{Add, AddAssign, Range, Sub, SubAssign},
+    slice,
+};
+
+use crate::{
+    error::{self, Error, Result},
+    store::Store,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(transparent)]
 pub struct NodeIdx(pub u32);
 
 impl NodeIdx {
@@ -86,6 +96,7 @@ impl SubAssign<u32> for NodeIdx {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(transparent)]
 pub struct EdgeIdx(pub u32);
 
 impl EdgeIdx {
@@ -168,76 +179,421 @@ impl SubAssign<u32> for EdgeIdx {
     }
 }
 
+/// The targets of all edges, ordered by source node.
+pub enum Edges {
+    /// One node index per edge.
+    Plain(Store<NodeIdx>),
+    /// Compressed adjacency lists, see [`Packed`].
+    Packed(Packed),
+}
+
+impl Edges {
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Plain(targets) => targets.len(),
+            Self::Packed(packed) => packed.len,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for Edges {
+    fn default() -> Self {
+        Self::Plain(Store::default())
+    }
+}
+
+/// Adjacency lists stored as varint-encoded gaps between sorted targets.
+///
+/// The first target of every node is encoded as-is, every following target as
+/// its distance to the previous one. Each value is written in little-endian
+/// base 128, with the high bit of a byte marking that more bytes follow. Since
+/// most targets of a node are close to each other after sorting, this usually
+/// needs one or two bytes per edge instead of four.
+pub struct Packed {
+    /// For every node, the offset in `bytes` where its adjacency list starts.
+    pub offsets: Store<u64>,
+    pub bytes: Store<u8>,
+    /// Number of edges.
+    pub len: usize,
+}
+
+impl Packed {
+    fn write_varint(bytes: &mut Vec<u8>, mut n: u32) {
+        while n >= 0x80 {
+            bytes.push(n as u8 | 0x80);
+            n >>= 7;
+        }
+        bytes.push(n as u8);
+    }
+
+    /// Returns `None` if the bytes end early or the value doesn't fit.
+    fn read_varint(bytes: &mut &[u8]) -> Option<u32> {
+        let mut n = 0_u32;
+        let mut shift = 0;
+        loop {
+            let (byte, rest) = bytes.split_first()?;
+            *bytes = rest;
+            // Only the lowest 4 bits of a fifth byte still fit.
+            if shift == 28 && byte & 0x70 != 0 {
+                return None;
+            }
+            n |= u32::from(byte & 0x7F) << shift;
+            if byte & 0x80 == 0 {
+                return Some(n);
+            }
+            shift += 7;
+            if shift >= 32 {
+                return None;
+            }
+        }
+    }
+}
+
+/// Iterator over the targets of a single node's edges.
+pub enum Targets<'a> {
+    Plain(iter::Copied<slice::Iter<'a, NodeIdx>>),
+    Packed {
+        bytes: &'a [u8],
+        prev: u32,
+        remaining: usize,
+    },
+}
+
+impl Iterator for Targets<'_> {
+    type Item = NodeIdx;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Self::Plain(targets) => targets.next(),
+            Self::Packed {
+                bytes,
+                prev,
+                remaining,
+            } => {
+                if *remaining == 0 {
+                    return None;
+                }
+                *remaining -= 1;
+                // Malformed lists are rejected when loading, see
+                // [`Graph::check_bounds`]. Should one slip through anyway, it
+                // ends early instead of crashing.
+                let Some(gap) = Packed::read_varint(bytes) else {
+                    *remaining = 0;
+                    return None;
+                };
+                *prev = prev.wrapping_add(gap);
+                Some(NodeIdx(*prev))
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self {
+            Self::Plain(targets) => targets.size_hint(),
+            Self::Packed { remaining, .. } => (*remaining, Some(*remaining)),
+        }
+    }
+}
+
+impl ExactSizeIterator for Targets<'_> {}
+
+/// The in-edges of every node, stored like the out-edges of a [`Graph`].
+pub struct Reverse {
+    /// A node points to the first of its in-edges.
+    pub nodes: Store<EdgeIdx>,
+    /// For every in-edge, its source node.
+    pub sources: Store<NodeIdx>,
+    /// For every in-edge, the corresponding edge of the graph.
+    pub edges: Store<EdgeIdx>,
+}
+
 #[derive(Default)]
 pub struct Graph {
     /// A node points to the first of its edges.
     ///
     /// A special case is that if the subsequent node points to the same edge,
     /// the current node has no edges.
-    pub nodes: Vec<EdgeIdx>,
+    pub nodes: Store<EdgeIdx>,
 
     /// An edge points to a target node.
     ///
     /// The source node is defined implicitly by the graph data structure.
-    pub edges: Vec<NodeIdx>,
+    pub edges: Edges,
+
+    /// Optional in-edges of every node, see [`Self::build_reverse`].
+    ///
+    /// Modifying the graph drops them.
+    pub reverse: Option<Reverse>,
 }
 
 impl Graph {
     pub fn with_capacity(nodes: usize, edges: usize) -> Self {
         Self {
-            nodes: Vec::with_capacity(nodes),
-            edges: Vec::with_capacity(edges),
+            nodes: Store::with_capacity(nodes),
+            edges: Edges::Plain(Store::with_capacity(edges)),
+            reverse: None,
         }
     }
 
     pub fn add_node(&mut self) {
+        self.reverse = None;
         self.nodes.push(EdgeIdx::new(self.edges.len()));
     }
 
+    /// Packed graphs are unpacked before the edge is added.
     pub fn add_edge(&mut self, target: NodeIdx) {
-        self.edges.push(target);
+        self.reverse = None;
+        self.unpack();
+        match &mut self.edges {
+            Edges::Plain(targets) => targets.push(target),
+            Edges::Packed(_) => unreachable!(),
+        }
     }
 
-    pub fn check_consistency(&self) {
-        if self.nodes.is_empty() {
-            assert!(self.edges.is_empty(), "edges must belong to existing nodes");
+    pub fn is_packed(&self) -> bool {
+        matches!(self.edges, Edges::Packed(_))
+    }
+
+    /// Compress the adjacency lists.
+    ///
+    /// The edges of every node must be sorted by target, see
+    /// [`crate::algo::sort_edges`].
+    pub fn pack(&mut self) {
+        let Edges::Plain(targets) = &self.edges else {
             return;
+        };
+
+        let mut offsets = Vec::with_capacity(self.nodes.len());
+        let mut bytes = vec![];
+        for node in self.nodes() {
+            offsets.push(bytes.len() as u64);
+            let mut prev = 0;
+            for target in &targets[self.edge_range(node)] {
+                assert!(target.0 >= prev, "edges must be sorted to be packed");
+                Packed::write_varint(&mut bytes, target.0 - prev);
+                prev = target.0;
+            }
         }
 
-        assert!(self.nodes.len() < u32::MAX as usize, "too many nodes");
-        assert!(self.edges.len() < u32::MAX as usize, "too many edges");
+        self.edges = Edges::Packed(Packed {
+            offsets: offsets.into(),
+            bytes: bytes.into(),
+            len: targets.len(),
+        });
+    }
 
-        assert_eq!(
-            *self.nodes.first().unwrap(),
-            EdgeIdx(0),
-            "first node pointer must be 0"
-        );
+    pub fn unpack(&mut self) {
+        if !self.is_packed() {
+            return;
+        }
+
+        let targets = self.edges().map(|(_, target)| target).collect::<Vec<_>>();
+        self.edges = Edges::Plain(targets.into());
+    }
+
+    /// Collect the in-edges of every node.
+    ///
+    /// The in-edges of a node are ordered by source, and then like the edges
+    /// of that source.
+    pub fn build_reverse(&mut self) {
+        let mut nodes = vec![EdgeIdx(0); self.nodes.len()];
+        for (_, target) in self.edges() {
+            if let Some(next) = nodes.get_mut(target.usize() + 1) {
+                *next += 1;
+            }
+        }
+        for i in 1..nodes.len() {
+            let prev = nodes[i - 1];
+            nodes[i] += prev;
+        }
+
+        let mut next = nodes.clone();
+        let mut sources = vec![NodeIdx::NONE; self.edges.len()];
+        let mut edges = vec![EdgeIdx(0); self.edges.len()];
+        for node in self.nodes() {
+            for (edge, target) in self.edge_targets(node) {
+                let i = &mut next[target.usize()];
+                sources[i.usize()] = node;
+                edges[i.usize()] = edge;
+                *i += 1;
+            }
+        }
+
+        self.reverse = Some(Reverse {
+            nodes: nodes.into(),
+            sources: sources.into(),
+            edges: edges.into(),
+        });
+    }
+
+    /// Swap the edges with the in-edges, which inverts the graph.
+    ///
+    /// Returns the previous edge corresponding to every new edge, or `None` if
+    /// there are no in-edges. Packed graphs are unpacked first.
+    pub fn swap_reverse(&mut self) -> Option<Store<EdgeIdx>> {
+        let reverse = self.reverse.take()?;
+        self.unpack();
+        let Edges::Plain(targets) = mem::take(&mut self.edges) else {
+            unreachable!()
+        };
+
+        let mut inverse = vec![EdgeIdx(0); reverse.edges.len()];
+        for (i, edge) in reverse.edges.iter().enumerate() {
+            inverse[edge.usize()] = EdgeIdx::new(i);
+        }
+
+        let nodes = mem::replace(&mut self.nodes, reverse.nodes);
+        self.edges = Edges::Plain(reverse.sources);
+        self.reverse = Some(Reverse {
+            nodes,
+            sources: targets,
+            edges: inverse.into(),
+        });
+
+        Some(reverse.edges)
+    }
+
+    /// Check that every node and edge index stored in the graph is in range,
+    /// so that using the graph can't panic.
+    ///
+    /// This is a single pass over the nodes and edges. Unlike
+    /// [`Self::check_consistency`], it doesn't check that the in-edges match
+    /// the edges.
+    pub fn check_bounds(&self) -> Result<()> {
+        if self.nodes.is_empty() {
+            return error::check(self.edges.is_empty(), "edges must belong to existing nodes");
+        }
+
+        error::check(self.nodes.len() < u32::MAX as usize, "too many nodes")?;
+        error::check(self.edges.len() < u32::MAX as usize, "too many edges")?;
+
+        error::check(
+            self.nodes.first() == Some(&EdgeIdx(0)),
+            "first node pointer must be 0",
+        )?;
 
         for (ni, node) in self.nodes.iter().cloned().enumerate() {
-            assert!(
+            error::check(
                 node.usize() <= self.edges.len(),
-                "node pointers must be in range"
-            );
+                "node pointers must be in range",
+            )?;
 
             if let Some(succ) = self.nodes.get(ni + 1) {
-                assert!(node <= *succ, "node pointers must be well-ordered");
+                error::check(node <= *succ, "node pointers must be well-ordered")?;
             }
         }
 
-        for edge in &self.edges {
-            assert!(
-                edge.usize() < self.nodes.len(),
-                "edge pointers must be in range"
-            );
+        if let Edges::Packed(packed) = &self.edges {
+            error::check(
+                packed.offsets.len() == self.nodes.len(),
+                "inconsistent number of packed offsets",
+            )?;
+
+            // Decode every adjacency list by hand so that malformed lists are
+            // reported instead of panicking in Targets.
+            for node in self.nodes() {
+                let offset = packed.offsets[node.usize()];
+                error::check(
+                    offset <= packed.bytes.len() as u64,
+                    "packed offsets must be in range",
+                )?;
+
+                let mut bytes = &packed.bytes[offset as usize..];
+                let mut prev = 0_u32;
+                for _ in self.edge_range(node) {
+                    prev = Packed::read_varint(&mut bytes)
+                        .and_then(|gap| prev.checked_add(gap))
+                        .ok_or_else(|| Error::datafile("malformed packed edges"))?;
+                }
+            }
+        }
+
+        for (_, target) in self.edges() {
+            error::check(
+                target.usize() < self.nodes.len(),
+                "edge pointers must be in range",
+            )?;
+        }
+
+        if let Some(reverse) = &self.reverse {
+            self.check_reverse_bounds(reverse)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn check_consistency(&self) -> Result<()> {
+        self.check_bounds()?;
+        if self.reverse.is_some() {
+            self.check_reverse()?;
+        }
+        Ok(())
+    }
+
+    fn check_reverse_bounds(&self, reverse: &Reverse) -> Result<()> {
+        error::check(
+            reverse.nodes.len() == self.nodes.len()
+                && reverse.sources.len() == self.edges.len()
+                && reverse.edges.len() == self.edges.len(),
+            "inconsistent number of in-edges",
+        )?;
+
+        error::check(
+            reverse.nodes.is_sorted()
+                && reverse.nodes.first().is_none_or(|n| *n == EdgeIdx(0))
+                && reverse
+                    .nodes
+                    .last()
+                    .is_none_or(|n| n.usize() <= self.edges.len()),
+            "in-edge pointers must be well-ordered and in range",
+        )?;
+
+        for source in &reverse.sources {
+            error::check(
+                source.usize() < self.nodes.len(),
+                "in-edge sources must be in range",
+            )?;
+        }
+        for edge in &reverse.edges {
+            error::check(
+                edge.usize() < self.edges.len(),
+                "in-edges must point to existing edges",
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Check that the in-edges match the edges.
+    fn check_reverse(&self) -> Result<()> {
+        let targets = self.edges().map(|(_, target)| target).collect::<Vec<_>>();
+        for node in self.nodes() {
+            for (edge, source) in self.in_edges(node) {
+                error::check(
+                    source.usize() < self.nodes.len()
+                        && self.edge_range(source).contains(&edge.usize())
+                        && targets[edge.usize()] == node,
+                    "in-edges must match edges",
+                )?;
+            }
         }
+
+        Ok(())
     }
 
     pub fn nodes(&self) -> impl Iterator<Item = NodeIdx> + '_ {
         (0..self.nodes.len()).map(NodeIdx::new)
     }
 
+    /// All edges as (source, target) pairs, ordered by source node.
     pub fn edges(&self) -> impl Iterator<Item = (NodeIdx, NodeIdx)> + '_ {
-        Edges::new(self)
+        self.nodes()
+            .flat_map(move |node| self.targets(node).map(move |target| (node, target)))
     }
 
     pub fn edge_start(&self, node: NodeIdx) -> EdgeIdx {
@@ -253,43 +609,142 @@ impl Graph {
         start.usize()..end.usize()
     }
 
-    pub fn edge_slice(&self, node: NodeIdx) -> &[NodeIdx] {
-        &self.edges[self.edge_range(node)]
+    /// The targets of a node's edges, in the same order as its
+    /// [`Self::edge_range`].
+    pub fn targets(&self, node: NodeIdx) -> Targets<'_> {
+        let range = self.edge_range(node);
+        match &self.edges {
+            Edges::Plain(targets) => Targets::Plain(targets[range].iter().copied()),
+            Edges::Packed(packed) => Targets::Packed {
+                bytes: &packed.bytes[packed.offsets[node.usize()] as usize..],
+                prev: 0,
+                remaining: range.len(),
+            },
+        }
     }
-}
 
-struct Edges<'a> {
-    graph: &'a Graph,
-    ni: NodeIdx,
-    ei: EdgeIdx,
+    /// A node's edges together with their targets.
+    pub fn edge_targets(&self, node: NodeIdx) -> impl Iterator<Item = (EdgeIdx, NodeIdx)> + '_ {
+        self.edge_range(node)
+            .map(EdgeIdx::new)
+            .zip(self.targets(node))
+    }
+
+    /// Requires the in-edges, see [`Self::build_reverse`].
+    pub fn in_edge_range(&self, node: NodeIdx) -> Range<usize> {
+        let reverse = self.reverse.as_ref().expect("in-edges must be built");
+        let start = reverse.nodes[node.usize()];
+        let end = reverse
+            .nodes
+            .get(node.usize() + 1)
+            .copied()
+            .unwrap_or_else(|| reverse.sources.len().into());
+        start.usize()..end.usize()
+    }
+
+    /// A node's in-edges as the corresponding edge and its source.
+    ///
+    /// Requires the in-edges, see [`Self::build_reverse`].
+    pub fn in_edges(&self, node: NodeIdx) -> impl Iterator<Item = (EdgeIdx, NodeIdx)> + '_ {
+        let reverse = self.reverse.as_ref().expect("in-edges must be built");
+        self.in_edge_range(node)
+            .map(|i| (reverse.edges[i], reverse.sources[i]))
+    }
 }
 
-impl<'a> Edges<'a> {
-    fn new(graph: &'a Graph) -> Self {
-        Self {
-            graph,
-            ni: NodeIdx(0),
-            ei: EdgeIdx(0),
+#[cfg(test)]
+impl Graph {
+    /// Build a small graph for tests from the targets of every node.
+    pub fn from_adjacency(adjacency: &[&[u32]]) -> Self {
+        let mut graph = Self::default();
+        for targets in adjacency {
+            graph.add_node();
+            for target in *targets {
+                graph.add_edge(NodeIdx(*target));
+            }
         }
+        graph
     }
 }
 
-impl Iterator for Edges<'_> {
-    type Item = (NodeIdx, NodeIdx);
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.ei.usize() >= self.graph.edges.len() {
-            return None;
+    fn read_all(mut bytes: &[u8]) -> Option<Vec<u32>> {
+        let mut result = vec![];
+        while !bytes.is_empty() {
+            result.push(Packed::read_varint(&mut bytes)?);
         }
-        let target = self.graph.edges[self.ei.usize()];
-
-        // if would not be sufficient because some nodes may not have any edges.
-        while self.ei >= self.graph.edge_start(self.ni + 1) {
-            self.ni += 1;
+        Some(result)
+    }
+
+    #[test]
+    fn varint_round_trip() {
+        let values = [
+            0,
+            1,
+            0x7F,
+            0x80,
+            0x3FFF,
+            0x4000,
+            0x0FFF_FFFF,
+            0x1000_0000,
+            u32::MAX,
+        ];
+        let mut bytes = vec![];
+        for n in values {
+            Packed::write_varint(&mut bytes, n);
         }
-        let source = self.ni;
+        assert_eq!(read_all(&bytes), Some(values.to_vec()));
+    }
+
+    #[test]
+    fn varint_rejects_malformed() {
+        // Ends while more bytes are announced
+        assert_eq!(read_all(&[0x80]), None);
+        // Fifth byte with bits beyond 32
+        assert_eq!(read_all(&[0xFF, 0xFF, 0xFF, 0xFF, 0x1F]), None);
+        assert_eq!(read_all(&[0x80, 0x80, 0x80, 0x80, 0x10]), None);
+        // Sixth byte
+        assert_eq!(read_all(&[0xFF, 0xFF, 0xFF, 0xFF, 0x8F, 0x00]), None);
+        // Largest value that fits
+        assert_eq!(
+            read_all(&[0xFF, 0xFF, 0xFF, 0xFF, 0x0F]),
+            Some(vec![u32::MAX])
+        );
+    }
+
+    fn adjacency(graph: &Graph) -> Vec<Vec<u32>> {
+        graph
+            .nodes()
+            .map(|n| graph.targets(n).map(|t| t.0).collect())
+            .collect()
+    }
+
+    #[test]
+    fn packed_round_trip() {
+        let lists: &[&[u32]] = &[&[1, 2, 2, 4], &[], &[0], &[0, 1, 2, 3, 4], &[], &[]];
+        let mut graph = Graph::from_adjacency(lists);
+        let plain = adjacency(&graph);
+
+        graph.pack();
+        assert!(graph.is_packed());
+        assert_eq!(graph.edges.len(), 10);
+        assert_eq!(adjacency(&graph), plain);
+        graph.check_consistency().unwrap();
+
+        graph.unpack();
+        assert!(!graph.is_packed());
+        assert_eq!(adjacency(&graph), plain);
+    }
 
-        self.ei += 1;
-        Some((source, target))
+    #[test]
+    fn packed_large_gaps() {
+        // Targets don't need to exist to be encoded.
+        let mut graph = Graph::from_adjacency(&[&[0, 200, 100_000, u32::MAX - 1], &[]]);
+        let plain = adjacency(&graph);
+        graph.pack();
+        assert_eq!(adjacency(&graph), plain);
     }
 }
@@ -0,0 +1,224 @@
+use std::ops::Range;
+
+use crate::{data::Pages, graph::NodeIdx, store::Store, util::TitleNormalizer};
+
+/// All pages, sorted by normalized title.
+///
+/// The normalized titles are stored alongside the order, back to back like
+/// [`Pages::titles`], so a lookup only normalizes the query and then compares
+/// bytes.
+#[derive(Default)]
+pub struct TitleIndex {
+    pub nodes: Store<NodeIdx>,
+    /// For every entry, the offset in `keys` where its normalized title ends.
+    pub key_ends: Store<u32>,
+    pub keys: Store<u8>,
+}
+
+impl TitleIndex {
+    pub fn build(normalizer: &TitleNormalizer, pages: &Pages) -> Self {
+        let mut entries = (0..pages.len())
+            .map(|i| {
+                (
+                    normalizer.normalize(pages.title(NodeIdx::new(i))),
+                    NodeIdx::new(i),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        entries.sort();
+
+        let mut result = Self::default();
+        let keys = result.keys.to_mut();
+        for (key, node) in entries {
+            keys.extend_from_slice(key.as_bytes());
+            let key_end = u32::try_from(keys.len()).expect("too many title key bytes");
+            result.nodes.push(node);
+            result.key_ends.push(key_end);
+        }
+        result
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// The normalized title of the entry at the given position.
+    pub fn key(&self, i: usize) -> &[u8] {
+        let start = if i == 0 { 0 } else { self.key_ends[i - 1] };
+        let end = self.key_ends[i];
+        &self.keys[start as usize..end as usize]
+    }
+
+    /// Position of the first entry for which `pred` is false, assuming all
+    /// entries for which it is true come first.
+    fn partition_point(&self, pred: impl Fn(&[u8]) -> bool) -> usize {
+        let (mut lo, mut hi) = (0, self.len());
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if pred(self.key(mid)) {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        lo
+    }
+
+    /// Position of the first entry whose normalized title is not less than the
+    /// given normalized title.
+    fn lower_bound(&self, normalized: &str) -> usize {
+        self.partition_point(|key| key < normalized.as_bytes())
+    }
+
+    /// Positions of the entries whose normalized title starts with the given
+    /// normalized prefix.
+    fn prefix_range(&self, normalized: &str) -> Range<usize> {
+        let prefix = normalized.as_bytes();
+        let start = self.lower_bound(normalized);
+        let end = self.partition_point(|key| key < prefix || key.starts_with(prefix));
+        start..end
+    }
+
+    /// Find the page with the given title.
+    ///
+    /// If multiple pages share a normalized title, the one that comes first in
+    /// the datafile is returned.
+    pub fn find(&self, normalizer: &TitleNormalizer, title: &str) -> Option<NodeIdx> {
+        let normalized = normalizer.normalize(title);
+        let i = self.lower_bound(&normalized);
+        (i < self.len() && self.key(i) == normalized.as_bytes()).then(|| self.nodes[i])
+    }
+
+    /// All pages whose normalized title starts with the normalized prefix, in
+    /// order of their normalized titles.
+    pub fn prefix(
+        &self,
+        normalizer: &TitleNormalizer,
+        prefix: &str,
+    ) -> impl Iterator<Item = NodeIdx> + '_ {
+        let normalized = normalizer.normalize(prefix);
+        self.prefix_range(&normalized).map(|i| self.nodes[i])
+    }
+
+    /// The distinct first characters of the normalized titles, in order.
+    pub fn first_chars(&self) -> Vec<char> {
+        let mut result = vec![];
+        let mut i = 0;
+        while i < self.len() {
+            let first = str::from_utf8(self.key(i))
+                .ok()
+                .and_then(|key| key.chars().next());
+            match first {
+                Some(first) => {
+                    result.push(first);
+                    i = self.prefix_range(first.encode_utf8(&mut [0; 4])).end;
+                }
+                None => i += 1,
+            }
+        }
+        result
+    }
+
+    /// Positions of up to `count` entries around the given normalized title.
+    ///
+    /// The entries share as long a prefix with the title as possible while
+    /// still filling `count`, but always at least its first character.
+    pub fn near(&self, normalized: &str, count: usize) -> Range<usize> {
+        let mut range = 0..self.len();
+        let ends = normalized
+            .char_indices()
+            .map(|(i, _)| i)
+            .skip(1)
+            .chain([normalized.len()])
+            .filter(|end| *end > 0)
+            .collect::<Vec<_>>();
+        for end in ends.into_iter().rev() {
+            range = self.prefix_range(&normalized[..end]);
+            if range.len() >= count {
+                break;
+            }
+        }
+
+        let last_start = range.end.saturating_sub(count).max(range.start);
+        let start = self
+            .lower_bound(normalized)
+            .saturating_sub(count / 2)
+            .clamp(range.start, last_start);
+        start..(start + count).min(range.end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::Page;
+
+    fn pages(titles: &[&str]) -> Pages {
+        let mut pages = Pages::default();
+        for (i, title) in titles.iter().enumerate() {
+            pages.push(Page {
+                id: i as u32,
+                title,
+                length: 0,
+                redirect: false,
+            });
+        }
+        pages
+    }
+
+    #[test]
+    fn find_normalized() {
+        let normalizer = TitleNormalizer::new();
+        let pages = pages(&["Foo bar", "Apple", "Foo", "Zebra", "Foo"]);
+        let index = TitleIndex::build(&normalizer, &pages);
+
+        assert_eq!(index.key(0), b"Apple");
+        assert_eq!(index.find(&normalizer, "foo_bar"), Some(NodeIdx(0)));
+        assert_eq!(index.find(&normalizer, " zebra "), Some(NodeIdx(3)));
+        // Duplicates resolve to the first page.
+        assert_eq!(index.find(&normalizer, "Foo"), Some(NodeIdx(2)));
+        assert_eq!(index.find(&normalizer, "Fo"), None);
+        assert_eq!(index.find(&normalizer, "Zz"), None);
+        assert_eq!(index.find(&normalizer, "A"), None);
+    }
+
+    #[test]
+    fn prefix_in_order() {
+        let normalizer = TitleNormalizer::new();
+        let pages = pages(&["Foo bar", "Apple", "Foo", "Food", "Fop"]);
+        let index = TitleIndex::build(&normalizer, &pages);
+
+        let found = index.prefix(&normalizer, "foo").collect::<Vec<_>>();
+        assert_eq!(found, [NodeIdx(2), NodeIdx(0), NodeIdx(3)]);
+        assert_eq!(index.prefix(&normalizer, "x").count(), 0);
+        assert_eq!(index.prefix(&normalizer, "").count(), 5);
+    }
+
+    #[test]
+    fn near_shares_longest_prefix() {
+        let normalizer = TitleNormalizer::new();
+        let pages = pages(&["Apple", "Bar", "Baz", "Bazaar", "Bo", "Cat"]);
+        let index = TitleIndex::build(&normalizer, &pages);
+
+        let keys = |range: Range<usize>| range.map(|i| index.key(i)).collect::<Vec<_>>();
+        assert_eq!(keys(index.near("Bazz", 2)), [b"Baz" as &[u8], b"Bazaar"]);
+        assert_eq!(
+            keys(index.near("Bazz", 3)),
+            [b"Bar" as &[u8], b"Baz", b"Bazaar"]
+        );
+        // Never beyond the first character.
+        assert_eq!(index.near("Bazz", 10).len(), 4);
+        assert_eq!(index.near("Dog", 10).len(), 0);
+        assert_eq!(index.near("", 4), 0..4);
+    }
+
+    #[test]
+    fn first_chars_distinct() {
+        let normalizer = TitleNormalizer::new();
+        let pages = pages(&["Bar", "Apple", "Ärger", "Baz", "", "Bo"]);
+        let index = TitleIndex::build(&normalizer, &pages);
+
+        assert_eq!(index.first_chars(), ['A', 'B', 'Ä']);
+    }
+}
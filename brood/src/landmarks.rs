@@ -0,0 +1,256 @@
+use std::{
+    cmp::Reverse,
+    convert::Infallible,
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+    sync::Arc,
+};
+
+use indicatif::ProgressBar;
+use memmap2::Mmap;
+
+use crate::{
+    algo::Dijkstra,
+    data::Data,
+    error::{Error, Result},
+    graph::NodeIdx,
+    store::Store,
+    util,
+};
+
+/// Marks the start of a landmarks file.
+const MAGIC: [u8; 8] = *b"BROODLMK";
+
+/// The current version of the landmarks file format.
+const VERSION: u32 = 1;
+
+/// Size of the magic bytes, version, fingerprint and counts at the start of a
+/// landmarks file.
+const HEADER_SIZE: u64 = MAGIC.len() as u64 + 16;
+
+/// Distances from and to a few landmark articles, used to estimate the
+/// distance between any two articles.
+///
+/// For every landmark `l`, the triangle inequality gives `d(l, goal) - d(l,
+/// node)` and `d(node, l) - d(goal, l)` as lower bounds of `d(node, goal)`, and
+/// `d(node, l) + d(l, goal)` as an upper bound. Following a link costs 1 unless
+/// it starts at a redirect, like everywhere else.
+///
+/// The distances only apply to the graph they were computed on, which is why
+/// the file contains its [`Data::fingerprint`]. Global filters change the
+/// graph.
+pub struct Landmarks {
+    pub nodes: Vec<NodeIdx>,
+    fingerprint: u32,
+    /// For every landmark, the distance from it to every node.
+    from: Vec<Store<u32>>,
+    /// For every landmark, the distance from every node to it.
+    to: Vec<Store<u32>>,
+}
+
+fn read_u32(r: &mut impl Read) -> io::Result<u32> {
+    let mut buf = [0_u8; 4];
+    r.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u32s(r: &mut impl Read, len: usize) -> io::Result<Vec<u32>> {
+    let mut buf = vec![0_u8; len * 4];
+    r.read_exact(&mut buf)?;
+    Ok(buf
+        .chunks_exact(4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect())
+}
+
+fn write_u32s(w: &mut impl Write, ns: &[u32]) -> io::Result<()> {
+    for n in ns {
+        w.write_all(&n.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+impl Landmarks {
+    /// Pick the non-redirect articles with the most links and inlinks as
+    /// landmarks, then find the distances from and to them.
+    ///
+    /// Requires the in-edges, see [`crate::graph::Graph::build_reverse`].
+    pub fn build(data: &Data, count: usize, jobs: usize) -> Self {
+        let graph = &data.graph;
+        let mut candidates = graph
+            .nodes()
+            .filter(|n| !data.pages.redirect(*n))
+            .map(|n| {
+                let degree = graph.edge_range(n).len() + graph.in_edge_range(n).len();
+                (Reverse(degree), n)
+            })
+            .collect::<Vec<_>>();
+        candidates.sort_unstable();
+        let nodes = candidates
+            .into_iter()
+            .take(count)
+            .map(|(_, n)| n)
+            .collect::<Vec<_>>();
+
+        // Every landmark needs one search forward and one backward.
+        let bar = ProgressBar::new(2 * nodes.len() as u64);
+        let mut from = vec![vec![]; nodes.len()];
+        let mut to = vec![vec![]; nodes.len()];
+        let cost = |source: NodeIdx, _edge, _target| !data.pages.redirect(source) as u32;
+        let Ok(()) = util::parallel(
+            jobs,
+            2 * nodes.len(),
+            || Dijkstra::new(graph),
+            |dijkstra, i| {
+                let landmark = nodes[i / 2];
+                if i % 2 == 1 {
+                    dijkstra.run_backward(landmark, cost);
+                } else {
+                    dijkstra.run(landmark, |_| false, cost);
+                }
+                graph.nodes().map(|n| dijkstra.cost(n)).collect()
+            },
+            |i, costs| -> std::result::Result<(), Infallible> {
+                if i % 2 == 1 {
+                    to[i / 2] = costs;
+                } else {
+                    from[i / 2] = costs;
+                }
+                bar.inc(1);
+                Ok(())
+            },
+        );
+        bar.finish_and_clear();
+
+        Self {
+            nodes,
+            fingerprint: data.fingerprint(),
+            from: from.into_iter().map(Store::from).collect(),
+            to: to.into_iter().map(Store::from).collect(),
+        }
+    }
+
+    pub fn write_to_file(&self, path: &Path) -> io::Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        file.write_all(&MAGIC)?;
+        let nodes = self.from.first().map_or(0, |costs| costs.len());
+        write_u32s(
+            &mut file,
+            &[
+                VERSION,
+                self.fingerprint,
+                nodes as u32,
+                self.nodes.len() as u32,
+            ],
+        )?;
+        write_u32s(
+            &mut file,
+            &self.nodes.iter().map(|n| n.0).collect::<Vec<_>>(),
+        )?;
+        for (from, to) in self.from.iter().zip(&self.to) {
+            write_u32s(&mut file, from)?;
+            write_u32s(&mut file, to)?;
+        }
+        file.flush()
+    }
+
+    /// Fails if the landmarks were computed on a different graph.
+    ///
+    /// The distances are memory-mapped.
+    pub fn read_from_file(path: &Path, data: &Data) -> Result<Self> {
+        let mut file = BufReader::new(File::open(path)?);
+        let invalid = |msg: &str| Error::Landmarks(msg.to_string());
+        let eof = |err: io::Error| match err.kind() {
+            io::ErrorKind::UnexpectedEof => invalid("unexpected end of data"),
+            _ => Error::Io(err),
+        };
+
+        let mut magic = [0_u8; MAGIC.len()];
+        file.read_exact(&mut magic).map_err(eof)?;
+        if magic != MAGIC {
+            return Err(invalid("not a landmarks file"));
+        }
+
+        let version = read_u32(&mut file).map_err(eof)?;
+        if version != VERSION {
+            return Err(Error::Landmarks(format!(
+                "unsupported version {version} (this brood supports version {VERSION})"
+            )));
+        }
+
+        let fingerprint = read_u32(&mut file).map_err(eof)?;
+        let nodes = read_u32(&mut file).map_err(eof)? as usize;
+        let count = read_u32(&mut file).map_err(eof)? as usize;
+        if nodes != data.graph.nodes.len() || fingerprint != data.fingerprint() {
+            return Err(invalid(
+                "computed on a different graph, the datafile or global filters have changed",
+            ));
+        }
+
+        // Every landmark has an index and two distances per node.
+        let file_len = file.get_ref().metadata()?.len();
+        let landmark_size = 4 + 8 * nodes as u64;
+        if count as u64 * landmark_size > file_len.saturating_sub(HEADER_SIZE) {
+            return Err(invalid("unexpected end of data"));
+        }
+
+        let landmarks = read_u32s(&mut file, count)
+            .map_err(eof)?
+            .into_iter()
+            .map(NodeIdx)
+            .collect::<Vec<_>>();
+        if landmarks.iter().any(|n| n.usize() >= nodes) {
+            return Err(invalid("landmarks must be in range"));
+        }
+
+        // SAFETY: The landmarks file must not be modified while brood is running.
+        let map = Arc::new(unsafe { Mmap::map(file.get_ref())? });
+        let mut offset = HEADER_SIZE + 4 * count as u64;
+        let mut next = || {
+            let costs =
+                Store::map(&map, offset, nodes).ok_or_else(|| invalid("unexpected end of data"));
+            offset += 4 * nodes as u64;
+            costs
+        };
+        let mut from = vec![];
+        let mut to = vec![];
+        for _ in 0..count {
+            from.push(next()?);
+            to.push(next()?);
+        }
+
+        Ok(Self {
+            nodes: landmarks,
+            fingerprint,
+            from,
+            to,
+        })
+    }
+
+    /// A distance that is never more than the distance from start to goal.
+    pub fn lower_bound(&self, start: NodeIdx, goal: NodeIdx) -> u32 {
+        let (start, goal) = (start.usize(), goal.usize());
+        let mut bound = 0;
+        for (from, to) in self.from.iter().zip(&self.to) {
+            if from[start] != u32::MAX && from[goal] != u32::MAX {
+                bound = bound.max(from[goal].saturating_sub(from[start]));
+            }
+            if to[start] != u32::MAX && to[goal] != u32::MAX {
+                bound = bound.max(to[start].saturating_sub(to[goal]));
+            }
+        }
+        bound
+    }
+
+    /// A distance that is never less than the distance from start to goal, if
+    /// any path through a landmark exists.
+    pub fn upper_bound(&self, start: NodeIdx, goal: NodeIdx) -> Option<u32> {
+        self.from
+            .iter()
+            .zip(&self.to)
+            .filter(|(from, to)| to[start.usize()] != u32::MAX && from[goal.usize()] != u32::MAX)
+            .map(|(from, to)| to[start.usize()].saturating_add(from[goal.usize()]))
+            .min()
+    }
+}
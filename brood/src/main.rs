@@ -1,23 +1,79 @@
 mod algo;
 mod commands;
 mod data;
+mod error;
 mod graph;
+mod index;
+mod landmarks;
+mod output;
+mod store;
 mod util;
 
-use std::{io, path::PathBuf};
+use std::{path::PathBuf, process::ExitCode};
 
 use clap::Parser;
-use data::Data;
+use data::{Data, Load};
+use error::Result;
+use output::Format;
 
 #[derive(Debug, Parser)]
 enum Command {
     Ingest(commands::ingest::Cmd),
     Export(commands::export::Cmd),
+    Info(commands::info::Cmd),
     Show(commands::show::Cmd),
+    Titles(commands::titles::Cmd),
     Stats(commands::stats::Cmd),
     Path(commands::path::Cmd),
+    PathBatch(commands::path_batch::Cmd),
+    SetPath(commands::set_path::Cmd),
+    Distances(commands::distances::Cmd),
+    Landmarks(commands::landmarks::Cmd),
+    Components(commands::components::Cmd),
+    BowTie(commands::bow_tie::Cmd),
+    Unlinked(commands::unlinked::Cmd),
+    Pagerank(commands::pagerank::Cmd),
     LongestPath(commands::longest_path::Cmd),
     Pg(commands::pg::Cmd),
+    Repl(commands::repl::Cmd),
+    Serve(commands::serve::Cmd),
+    Browse(commands::browse::Cmd),
+}
+
+impl Command {
+    /// How much of the datafile the command needs.
+    fn load(&self) -> Load {
+        match self {
+            Self::Ingest(_) | Self::Info(_) | Self::Export(_) => Load::Full,
+            Self::Show(_)
+            | Self::Titles(_)
+            | Self::PathBatch(_)
+            | Self::SetPath(_)
+            | Self::Distances(_)
+            | Self::Landmarks(_)
+            | Self::Components(_)
+            | Self::BowTie(_)
+            | Self::Unlinked(_)
+            | Self::Pagerank(_)
+            | Self::LongestPath(_) => Load::Graph,
+            Self::Path(cmd) => cmd.load(),
+            Self::Stats(cmd) => cmd.load(),
+            Self::Pg(_) | Self::Repl(_) | Self::Serve(_) | Self::Browse(_) => Load::Full,
+        }
+    }
+
+    /// Whether the command needs the in-edges of the graph.
+    fn in_edges(&self) -> bool {
+        match self {
+            Self::Path(cmd) => cmd.in_edges(),
+            Self::Landmarks(_)
+            | Self::BowTie(_)
+            | Self::Pagerank(_)
+            | Self::Serve(_)
+            | Self::Browse(_) => true,
+            _ => false,
+        }
+    }
 }
 
 #[derive(Debug, Parser)]
@@ -35,21 +91,45 @@ struct Args {
     invert_edges: bool,
     #[arg(long, short)]
     check_consistency: bool,
+    /// Don't verify the checksums of the small sections of the datafile.
+    #[arg(long)]
+    no_verify: bool,
+    /// How to print results. Progress is always printed to stderr.
+    #[arg(long, short, global = true, value_enum, default_value_t)]
+    format: Format,
 }
 
-fn main() -> io::Result<()> {
+fn main() -> ExitCode {
     let args = Args::parse();
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("Error: {err}");
+            err.exit_code()
+        }
+    }
+}
 
+fn run(args: Args) -> Result<()> {
     if let Command::Ingest(cmd) = &args.command {
         return cmd.run(&args.datafile);
     }
 
-    println!(">> Import");
-    println!("> Reading data");
-    let mut data = Data::read_from_file(&args.datafile)?;
+    if let Command::Info(cmd) = &args.command {
+        return cmd.run(&args.datafile, args.format);
+    }
 
+    eprintln!(">> Import");
+    let mut load = args.command.load();
     if args.in_parens.is_some() || args.in_structure.is_some() {
-        println!("> Filtering edges");
+        load = load.max(Load::Flags);
+    }
+
+    eprintln!("> Reading data");
+    let mut data = Data::read_from_file(&args.datafile, load, !args.no_verify)?;
+
+    if args.in_parens.is_some() || args.in_structure.is_some() {
+        eprintln!("> Filtering edges");
         algo::retain_edges(&mut data, |link| {
             args.in_parens.is_none_or(|b| b == link.in_parens())
                 && args.in_structure.is_none_or(|b| b == link.in_structure())
@@ -57,27 +137,44 @@ fn main() -> io::Result<()> {
     }
 
     if args.resolve_redirects {
-        println!("> Resolving redirects");
+        eprintln!("> Resolving redirects");
         algo::resolve_redirects(&mut data);
     }
 
     if args.invert_edges {
-        println!("> Inverting edges");
+        eprintln!("> Inverting edges");
         algo::invert(&mut data);
     }
 
+    if args.command.in_edges() && data.graph.reverse.is_none() {
+        eprintln!("> Collecting in-edges");
+        data.graph.build_reverse();
+    }
+
     if args.check_consistency {
-        println!("> Checking consistencey");
-        data.check_consistency();
+        eprintln!("> Checking consistencey");
+        data.check_consistency()?;
     }
 
     match args.command {
-        Command::Ingest(_) => unreachable!(),
+        Command::Ingest(_) | Command::Info(_) => unreachable!(),
         Command::Export(cmd) => cmd.run(data),
-        Command::Show(cmd) => cmd.run(data),
-        Command::Stats(cmd) => cmd.run(data),
-        Command::Path(cmd) => cmd.run(data),
-        Command::LongestPath(cmd) => cmd.run(data),
-        Command::Pg(cmd) => cmd.run(data),
+        Command::Show(cmd) => cmd.run(&data, args.format),
+        Command::Titles(cmd) => cmd.run(&data, args.format),
+        Command::Stats(cmd) => cmd.run(&data, args.format),
+        Command::Path(cmd) => cmd.run(&data, args.format),
+        Command::PathBatch(cmd) => cmd.run(&data, args.format),
+        Command::SetPath(cmd) => cmd.run(&data, args.format),
+        Command::Distances(cmd) => cmd.run(&data, args.format),
+        Command::Landmarks(cmd) => cmd.run(&data, args.format),
+        Command::Components(cmd) => cmd.run(&data, args.format),
+        Command::BowTie(cmd) => cmd.run(&data, args.format),
+        Command::Unlinked(cmd) => cmd.run(&data, args.format),
+        Command::Pagerank(cmd) => cmd.run(&data, args.format),
+        Command::LongestPath(cmd) => cmd.run(&data, args.format),
+        Command::Pg(cmd) => cmd.run(&data, args.format),
+        Command::Repl(cmd) => cmd.run(data, args.format),
+        Command::Serve(cmd) => cmd.run(&data),
+        Command::Browse(cmd) => cmd.run(&data),
     }
 }
@@ -0,0 +1,123 @@
+use std::{
+    fmt::Display,
+    io::{self, BufWriter, StdoutLock, Write},
+};
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::{data::Data, graph::NodeIdx};
+
+/// How commands print their results.
+///
+/// Progress is always printed to stderr, so only the results end up on stdout.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Format {
+    /// Aligned text for humans.
+    #[default]
+    Text,
+    /// A single JSON value.
+    Json,
+    /// A header row followed by one row per record.
+    Csv,
+}
+
+pub fn page(data: &Data, node: NodeIdx) -> Value {
+    let page = data.pages.get(node);
+    json!({ "title": page.title, "redirect": page.redirect })
+}
+
+pub fn pages(data: &Data, nodes: impl IntoIterator<Item = NodeIdx>) -> Value {
+    nodes.into_iter().map(|n| page(data, n)).collect()
+}
+
+/// A path as found by dijkstra, or null if there is none.
+pub fn path(data: &Data, path: Option<(u32, Vec<NodeIdx>)>) -> Value {
+    match path {
+        Some((cost, path)) => json!({
+            "cost": cost,
+            "hops": path.len() - 1,
+            "path": pages(data, path),
+        }),
+        None => Value::Null,
+    }
+}
+
+pub fn print_json(value: &impl Serialize) -> io::Result<()> {
+    let mut writer = BufWriter::new(io::stdout().lock());
+    serde_json::to_writer_pretty(&mut writer, value)?;
+    writeln!(writer)?;
+    writer.flush()
+}
+
+/// Fields containing separators, quotes or line breaks are quoted.
+pub fn write_csv_row(writer: &mut impl Write, fields: &[&dyn Display]) -> io::Result<()> {
+    for (i, field) in fields.iter().enumerate() {
+        if i > 0 {
+            write!(writer, ",")?;
+        }
+        let field = field.to_string();
+        if field.contains([',', '"', '\n', '\r']) {
+            write!(writer, "\"{}\"", field.replace('"', "\"\""))?;
+        } else {
+            write!(writer, "{field}")?;
+        }
+    }
+    writeln!(writer)
+}
+
+pub const PATH_HEADER: &[&str] = &["start", "goal", "cost", "hops", "step", "title", "redirect"];
+
+/// Writes CSV rows to stdout.
+pub struct Csv {
+    writer: BufWriter<StdoutLock<'static>>,
+}
+
+impl Csv {
+    pub fn new(header: &[&str]) -> io::Result<Self> {
+        let mut csv = Self {
+            writer: BufWriter::new(io::stdout().lock()),
+        };
+        let header = header.iter().map(|h| h as &dyn Display).collect::<Vec<_>>();
+        csv.row(&header)?;
+        Ok(csv)
+    }
+
+    pub fn row(&mut self, fields: &[&dyn Display]) -> io::Result<()> {
+        write_csv_row(&mut self.writer, fields)
+    }
+
+    /// One row per page of a path between start and goal, or a single row
+    /// with empty fields if there is none. Fits the [`PATH_HEADER`].
+    pub fn path_rows(
+        &mut self,
+        data: &Data,
+        start: NodeIdx,
+        goal: NodeIdx,
+        path: Option<(u32, Vec<NodeIdx>)>,
+    ) -> io::Result<()> {
+        let start = data.pages.title(start);
+        let goal = data.pages.title(goal);
+        let Some((cost, path)) = path else {
+            return self.row(&[&start, &goal, &"", &"", &"", &"", &""]);
+        };
+        let hops = path.len() - 1;
+        for (step, node) in path.into_iter().enumerate() {
+            let page = data.pages.get(node);
+            self.row(&[
+                &start,
+                &goal,
+                &cost,
+                &hops,
+                &step,
+                &page.title,
+                &page.redirect,
+            ])?;
+        }
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
@@ -0,0 +1,170 @@
+use std::{marker::PhantomData, mem, ops::Deref, slice, sync::Arc};
+
+use memmap2::Mmap;
+
+use crate::graph::{EdgeIdx, NodeIdx};
+
+/// Types that can be borrowed directly from the bytes of a datafile.
+///
+/// # Safety
+///
+/// Implementors must be plain old data: every bit pattern must be a valid
+/// value, and the in-memory layout must match the little-endian on-disk
+/// encoding on little-endian targets.
+pub unsafe trait Pod: Copy + 'static {
+    fn le_to_ne(self) -> Self;
+}
+
+unsafe impl Pod for u8 {
+    fn le_to_ne(self) -> Self {
+        self
+    }
+}
+
+unsafe impl Pod for u32 {
+    fn le_to_ne(self) -> Self {
+        u32::from_le(self)
+    }
+}
+
+unsafe impl Pod for u64 {
+    fn le_to_ne(self) -> Self {
+        u64::from_le(self)
+    }
+}
+
+unsafe impl Pod for NodeIdx {
+    fn le_to_ne(self) -> Self {
+        Self(u32::from_le(self.0))
+    }
+}
+
+unsafe impl Pod for EdgeIdx {
+    fn le_to_ne(self) -> Self {
+        Self(u32::from_le(self.0))
+    }
+}
+
+pub struct Mapped<T> {
+    map: Arc<Mmap>,
+    offset: usize,
+    len: usize,
+    _type: PhantomData<T>,
+}
+
+/// A slice of values that is either owned or borrowed from a memory-mapped
+/// datafile.
+///
+/// Mapped stores are copied into an owned buffer the first time they are
+/// modified, so algorithms can treat both kinds the same.
+pub enum Store<T: Pod> {
+    Owned(Vec<T>),
+    Mapped(Mapped<T>),
+}
+
+impl<T: Pod> Store<T> {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::Owned(Vec::with_capacity(capacity))
+    }
+
+    /// Collect the values at the given indices into a new owned store.
+    ///
+    /// An empty store stays empty, which allows selecting from columns that
+    /// were not loaded.
+    pub fn select(&self, indices: impl IntoIterator<Item = usize>) -> Self {
+        if self.is_empty() {
+            return Self::default();
+        }
+
+        Self::Owned(indices.into_iter().map(|i| self[i]).collect())
+    }
+
+    /// Borrow `len` values starting at byte `offset` of the map.
+    ///
+    /// Returns `None` if the range is out of bounds or not properly aligned. On
+    /// big-endian targets, the values are copied and converted instead.
+    pub fn map(map: &Arc<Mmap>, offset: u64, len: usize) -> Option<Self> {
+        let offset = usize::try_from(offset).ok()?;
+        let end = len
+            .checked_mul(mem::size_of::<T>())
+            .and_then(|n| n.checked_add(offset))?;
+
+        if end > map.len() {
+            return None;
+        }
+
+        if !(map.as_ptr() as usize + offset).is_multiple_of(mem::align_of::<T>()) {
+            return None;
+        }
+
+        let mapped = Mapped {
+            map: map.clone(),
+            offset,
+            len,
+            _type: PhantomData,
+        };
+
+        if cfg!(target_endian = "big") {
+            let values = mapped.as_slice().iter().map(|v: &T| v.le_to_ne()).collect();
+            return Some(Self::Owned(values));
+        }
+
+        Some(Self::Mapped(mapped))
+    }
+
+    pub fn to_mut(&mut self) -> &mut Vec<T> {
+        if let Self::Mapped(mapped) = self {
+            *self = Self::Owned(mapped.as_slice().to_vec());
+        }
+
+        match self {
+            Self::Owned(values) => values,
+            Self::Mapped(_) => unreachable!(),
+        }
+    }
+
+    pub fn push(&mut self, value: T) {
+        self.to_mut().push(value);
+    }
+}
+
+impl<T: Pod> Mapped<T> {
+    fn as_slice(&self) -> &[T] {
+        // SAFETY: Store::map checked bounds and alignment, T is Pod, and the
+        // map lives at least as long as self. The datafile must not be
+        // modified while it is mapped.
+        unsafe { slice::from_raw_parts(self.map.as_ptr().add(self.offset).cast(), self.len) }
+    }
+}
+
+impl<T: Pod> Default for Store<T> {
+    fn default() -> Self {
+        Self::Owned(vec![])
+    }
+}
+
+impl<T: Pod> Deref for Store<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Self::Owned(values) => values,
+            Self::Mapped(mapped) => mapped.as_slice(),
+        }
+    }
+}
+
+impl<'a, T: Pod> IntoIterator for &'a Store<T> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T: Pod> From<Vec<T>> for Store<T> {
+    fn from(value: Vec<T>) -> Self {
+        Self::Owned(value)
+    }
+}
@@ -1,10 +1,23 @@
-use std::{collections::HashSet, fmt};
+use std::{
+    collections::HashSet,
+    fmt,
+    fs::File,
+    io::{self, BufRead, BufReader, IsTerminal, Write},
+    mem,
+    path::Path,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc,
+    },
+    thread,
+};
 
 use regex::Regex;
 
 use crate::{
     data::{Data, Page},
-    graph::NodeIdx,
+    error::{Error, Result},
+    graph::{EdgeIdx, NodeIdx},
 };
 
 // https://github.com/wikimedia/mediawiki-title/blob/6880ae1a9ffdfa2eea9fd75b472493a67dabcc48/lib/mediawiki.Title.phpCharToUpper.js
@@ -116,14 +129,220 @@ impl TitleNormalizer {
     }
 }
 
-pub fn locate_title(normalizer: &TitleNormalizer, data: &Data, title: &str) -> NodeIdx {
+/// Find the page with the given title.
+///
+/// Uses the title index if the datafile has one.
+pub fn find_title(normalizer: &TitleNormalizer, data: &Data, title: &str) -> Option<NodeIdx> {
+    match &data.title_index {
+        Some(index) => index.find(normalizer, title),
+        None => {
+            let normalized = normalizer.normalize(title);
+            data.pages
+                .iter()
+                .enumerate()
+                .find(|(_, p)| normalizer.normalize(p.title) == normalized)
+                .map(|(i, _)| NodeIdx::new(i))
+        }
+    }
+}
+
+/// The error for a title without a page, including suggestions.
+pub fn title_not_found(normalizer: &TitleNormalizer, data: &Data, title: &str) -> Error {
+    let suggestions = suggest_titles(normalizer, data, title, 10);
+    not_found(data, title, suggestions)
+}
+
+fn not_found(data: &Data, title: &str, suggestions: Vec<NodeIdx>) -> Error {
+    Error::ArticleNotFound {
+        title: title.to_string(),
+        suggestions: suggestions
+            .into_iter()
+            .map(|node| fmt_suggestion(data, node))
+            .collect(),
+    }
+}
+
+/// Find the page with the given title, see [`find_title`].
+///
+/// If there is no such page, similar titles are suggested. In an interactive
+/// terminal, the user may pick one of them instead.
+pub fn locate_title(normalizer: &TitleNormalizer, data: &Data, title: &str) -> Result<NodeIdx> {
+    if let Some(node) = find_title(normalizer, data, title) {
+        return Ok(node);
+    }
+
+    let suggestions = suggest_titles(normalizer, data, title, 10);
+    if !suggestions.is_empty() && io::stdin().is_terminal() && io::stderr().is_terminal() {
+        if let Some(node) = pick_suggestion(data, title, &suggestions)? {
+            return Ok(node);
+        }
+    }
+
+    Err(not_found(data, title, suggestions))
+}
+
+/// Levenshtein distance between two strings, or `None` if it exceeds `max`.
+fn edit_distance(a: &[char], b: &[char], max: usize) -> Option<usize> {
+    if a.len().abs_diff(b.len()) > max {
+        return None;
+    }
+
+    let mut prev = (0..=b.len()).collect::<Vec<_>>();
+    let mut curr = vec![0; b.len() + 1];
+    for (i, ca) in a.iter().enumerate() {
+        curr[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let replace = prev[j] + (ca != cb) as usize;
+            curr[j + 1] = replace.min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        if curr.iter().all(|d| *d > max) {
+            return None;
+        }
+        mem::swap(&mut prev, &mut curr);
+    }
+
+    Some(prev[b.len()]).filter(|d| *d <= max)
+}
+
+/// Maximum number of titles compared to the given title when suggesting
+/// titles, see [`suggest_titles`].
+const MAX_SUGGESTION_CANDIDATES: usize = 1000;
+
+/// Maximum number of titles compared to the given title for every guess at a
+/// typo in its first character, see [`suggest_titles`].
+const MAX_TYPO_CANDIDATES: usize = 10;
+
+/// Find pages with titles similar to the given title, best match first.
+///
+/// Titles starting with the given title (ignoring case) are ranked highest,
+/// followed by titles within a small edit distance. Every article is suggested
+/// at most once, even if several redirects to it match.
+///
+/// With a title index, only a limited number of titles sharing a prefix with
+/// the given title are considered, plus a few sharing a prefix with the title
+/// without its first character, or with any other first character. Without
+/// one, all titles are compared.
+pub fn suggest_titles(
+    normalizer: &TitleNormalizer,
+    data: &Data,
+    title: &str,
+    limit: usize,
+) -> Vec<NodeIdx> {
     let normalized = normalizer.normalize(title);
-    data.pages
-        .iter()
-        .enumerate()
-        .find(|(_, p)| normalizer.normalize(&p.title) == normalized)
-        .map(|(i, _)| NodeIdx::new(i))
-        .expect("invalid title")
+    let titles = match &data.title_index {
+        Some(index) => {
+            let mut positions = index
+                .near(&normalized, MAX_SUGGESTION_CANDIDATES)
+                .collect::<Vec<_>>();
+
+            // A typo in the first character puts the title somewhere else in
+            // the index entirely.
+            let mut chars = normalized.chars();
+            let first = chars.next();
+            let rest = chars.as_str();
+            if !rest.is_empty() {
+                let dropped = normalizer.normalize(rest);
+                positions.extend(index.near(&dropped, MAX_TYPO_CANDIDATES));
+                for c in index.first_chars() {
+                    if Some(c) != first {
+                        let replaced = format!("{c}{rest}");
+                        positions.extend(index.near(&replaced, MAX_TYPO_CANDIDATES));
+                    }
+                }
+            }
+            positions.sort_unstable();
+            positions.dedup();
+
+            positions
+                .into_iter()
+                .map(|i| {
+                    (
+                        index.nodes[i],
+                        String::from_utf8_lossy(index.key(i)).into_owned(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        }
+        None => data
+            .pages
+            .iter()
+            .enumerate()
+            .map(|(i, p)| (NodeIdx::new(i), normalizer.normalize(p.title)))
+            .collect(),
+    };
+
+    let query = normalized.to_lowercase();
+    let query_chars = query.chars().collect::<Vec<_>>();
+    let max_distance = (query_chars.len() / 3).max(2);
+
+    let mut candidates = vec![];
+    for (node, candidate) in titles {
+        let candidate = candidate.to_lowercase();
+        let rank = if candidate.starts_with(&query) {
+            (0, candidate.len() - query.len())
+        } else {
+            let chars = candidate.chars().collect::<Vec<_>>();
+            match edit_distance(&query_chars, &chars, max_distance) {
+                Some(distance) => (1, distance),
+                None => continue,
+            }
+        };
+        candidates.push((rank, data.pages.redirect(node), node));
+    }
+
+    // Prefer articles over redirects with the same rank.
+    candidates.sort();
+
+    let mut seen = HashSet::new();
+    candidates
+        .into_iter()
+        .map(|(_, _, node)| node)
+        .filter(|node| seen.insert(resolve_redirects(data, *node)))
+        .take(limit)
+        .collect()
+}
+
+fn fmt_suggestion(data: &Data, node: NodeIdx) -> String {
+    let target = resolve_redirects(data, node);
+    if target == node {
+        data.pages.title(node).to_string()
+    } else {
+        format!(
+            "{} (redirects to {})",
+            data.pages.title(node),
+            data.pages.title(target)
+        )
+    }
+}
+
+/// Let the user choose one of the suggestions on stdin.
+fn pick_suggestion(data: &Data, title: &str, suggestions: &[NodeIdx]) -> Result<Option<NodeIdx>> {
+    let mut stderr = io::stderr().lock();
+    writeln!(stderr, "Article {title:?} not found. Did you mean:")?;
+    for (i, node) in suggestions.iter().enumerate() {
+        writeln!(stderr, "{:>4}. {}", i + 1, fmt_suggestion(data, *node))?;
+    }
+
+    loop {
+        write!(
+            stderr,
+            "Pick an article (1-{}), or nothing to abort: ",
+            suggestions.len()
+        )?;
+        stderr.flush()?;
+
+        let mut line = String::new();
+        io::stdin().lock().read_line(&mut line)?;
+        let line = line.trim();
+        if line.is_empty() {
+            return Ok(None);
+        }
+
+        match line.parse::<usize>() {
+            Ok(i) if (1..=suggestions.len()).contains(&i) => return Ok(Some(suggestions[i - 1])),
+            _ => writeln!(stderr, "Invalid choice: {line:?}")?,
+        }
+    }
 }
 
 pub fn resolve_redirects(data: &Data, node: NodeIdx) -> NodeIdx {
@@ -133,10 +352,7 @@ pub fn resolve_redirects(data: &Data, node: NodeIdx) -> NodeIdx {
     seen.insert(curr);
     while let Some(target) = data.redirect_target(curr) {
         if seen.contains(&target) {
-            println!(
-                "  Redirect cycle deteted: {:?}",
-                data.pages[node.usize()].title
-            );
+            eprintln!("  Redirect cycle deteted: {:?}", data.pages.title(node));
             break;
         }
 
@@ -147,8 +363,64 @@ pub fn resolve_redirects(data: &Data, node: NodeIdx) -> NodeIdx {
     curr
 }
 
-pub fn resolve_title(normalizer: &TitleNormalizer, data: &Data, title: &str) -> NodeIdx {
-    resolve_redirects(data, locate_title(normalizer, data, title))
+pub fn resolve_title(normalizer: &TitleNormalizer, data: &Data, title: &str) -> Result<NodeIdx> {
+    Ok(resolve_redirects(
+        data,
+        locate_title(normalizer, data, title)?,
+    ))
+}
+
+/// Resolve a file with one title per line, see [`resolve_title`].
+///
+/// Empty lines are skipped. Unlike [`resolve_title`], the user is never asked
+/// to pick a suggestion.
+pub fn resolve_title_file(
+    normalizer: &TitleNormalizer,
+    data: &Data,
+    file: &Path,
+) -> Result<Vec<NodeIdx>> {
+    let mut nodes = vec![];
+    for (i, line) in BufReader::new(File::open(file)?).lines().enumerate() {
+        let line = line?;
+        let title = line.trim();
+        if title.is_empty() {
+            continue;
+        }
+
+        let node = find_title(normalizer, data, title).ok_or_else(|| Error::Input {
+            line: i + 1,
+            msg: format!("article not found: {title:?}"),
+        })?;
+        nodes.push(resolve_redirects(data, node));
+    }
+    Ok(nodes)
+}
+
+/// All edges pointing to a node, together with their sources.
+///
+/// Without in-edges, this has to look at every edge of the graph.
+pub fn in_edges(data: &Data, node: NodeIdx) -> Vec<(EdgeIdx, NodeIdx)> {
+    if data.graph.reverse.is_some() {
+        return data.graph.in_edges(node).collect();
+    }
+
+    let mut result = vec![];
+    for source in data.graph.nodes() {
+        for (edge, target) in data.graph.edge_targets(source) {
+            if target == node {
+                result.push((edge, source));
+            }
+        }
+    }
+    result
+}
+
+/// The sources of all edges pointing to a node, see [`in_edges`].
+pub fn inlinks(data: &Data, node: NodeIdx) -> Vec<NodeIdx> {
+    in_edges(data, node)
+        .into_iter()
+        .map(|(_, source)| source)
+        .collect()
 }
 
 pub fn fmt_page(page: &Page) -> String {
@@ -158,3 +430,89 @@ pub fn fmt_page(page: &Page) -> String {
         format!("- {}", page.title)
     }
 }
+
+/// How many threads a command runs its work on.
+#[derive(Debug, clap::Args)]
+pub struct Jobs {
+    /// Number of threads, defaults to the number of cores.
+    #[arg(long, short)]
+    jobs: Option<usize>,
+}
+
+impl Jobs {
+    pub fn get(&self) -> usize {
+        match self.jobs {
+            Some(jobs) => jobs.max(1),
+            None => thread::available_parallelism().map_or(1, |n| n.get()),
+        }
+    }
+}
+
+/// Run `work` for every index in `0..count` on `jobs` threads.
+///
+/// Every thread creates its own state with `init`, e.g. a search whose buffers
+/// can be reused. The results are passed to `handle` on the calling thread as
+/// they arrive, which is not necessarily in order. If `handle` fails, the
+/// remaining work is abandoned and the error returned.
+pub fn parallel<S, T: Send, E>(
+    jobs: usize,
+    count: usize,
+    init: impl Fn() -> S + Sync,
+    work: impl Fn(&mut S, usize) -> T + Sync,
+    mut handle: impl FnMut(usize, T) -> std::result::Result<(), E>,
+) -> std::result::Result<(), E> {
+    let next = AtomicUsize::new(0);
+    let (tx, rx) = mpsc::channel();
+
+    thread::scope(|s| {
+        for _ in 0..jobs.max(1) {
+            let tx = tx.clone();
+            let (init, work, next) = (&init, &work, &next);
+            s.spawn(move || {
+                let mut state = init();
+                loop {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    if i >= count {
+                        break;
+                    }
+                    if tx.send((i, work(&mut state, i))).is_err() {
+                        break; // Handling failed
+                    }
+                }
+            });
+        }
+        drop(tx);
+
+        for (i, result) in rx {
+            handle(i, result)?;
+        }
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{data::Page, index::TitleIndex};
+
+    #[test]
+    fn suggest_first_letter_typos() {
+        let normalizer = TitleNormalizer::new();
+        let mut data = Data::new();
+        let titles = ["Berlin", "Xanadu", "Xerxes", "Paris", "Erlangen"];
+        for (i, title) in titles.into_iter().enumerate() {
+            data.pages.push(Page {
+                id: i as u32,
+                title,
+                length: 0,
+                redirect: false,
+            });
+        }
+        data.title_index = Some(TitleIndex::build(&normalizer, &data.pages));
+
+        let suggest = |title| suggest_titles(&normalizer, &data, title, 1);
+        assert_eq!(suggest("Xerlin"), [NodeIdx(0)]);
+        assert_eq!(suggest("xberlin"), [NodeIdx(0)]);
+        assert_eq!(suggest("aris"), [NodeIdx(3)]);
+    }
+}